@@ -1,8 +1,10 @@
+use super::utils::deserialize_optional_cow_str;
 use super::TextID;
 use derive_more::*;
 use lazy_static::*;
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
 use std::net::Ipv4Addr;
 use std::num::{NonZeroU16, NonZeroU8};
@@ -11,7 +13,53 @@ use std::str::FromStr;
 lazy_static! {
     static ref IP_REGEX: Regex =
         Regex::new(r#"^\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3}:\d{1,5}$"#).unwrap();
-    static ref TTY_REGEX: Regex = Regex::new(r#"^tty\w+$"#).unwrap();
+    static ref TTY_REGEX: Regex = Regex::new(r#"^(/dev/)?tty\w+$"#).unwrap();
+}
+
+/// Check if a decimal number string has leading zeros (e.g. `007`).
+fn has_leading_zeros(text: &str) -> bool {
+    text.len() > 1 && text.starts_with('0')
+}
+
+/// Parse an IP v.4 address, allowing leading zeros in the octets unless `strict` is set.
+///
+/// `Ipv4Addr::from_str` rejects leading zeros, so the octets are parsed manually.
+fn parse_ipv4(text: &str, strict: bool) -> Option<Ipv4Addr> {
+    let mut octets = [0_u8; 4];
+    let mut parts = text.split('.');
+
+    for octet in octets.iter_mut() {
+        let part = parts.next()?;
+
+        if part.is_empty() || !part.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        if strict && has_leading_zeros(part) {
+            return None;
+        }
+
+        *octet = u8::from_str(part).ok()?;
+    }
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(Ipv4Addr::from(octets))
+}
+
+/// The transport type of a controller's [`Address`].
+///
+/// [`Address`]: enum.Address.html
+///
+#[derive(Debug, Display, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Transport {
+    /// Network-connected (e.g. an IP address).
+    Network,
+    /// Serial-connected (e.g. a COM port or tty device).
+    Serial,
+    /// Unknown transport.
+    Unknown,
 }
 
 /// A data structure holding a controller's physical address.
@@ -30,7 +78,7 @@ pub enum Address<'a> {
     #[display(fmt = "COM{}", _0)]
     ComPort(NonZeroU8),
     //
-    /// A UNIX-style tty serial port device.
+    /// A UNIX-style tty serial port device (e.g. `ttyS0`), without the `/dev/` prefix.
     #[display(fmt = "{}", _0)]
     TtyDevice(TextID<'a>),
 }
@@ -38,6 +86,9 @@ pub enum Address<'a> {
 impl<'a> Address<'a> {
     /// Create a new `Address::IPv4` from an IP address string and port number.
     ///
+    /// Leading zeros are allowed in the IP address octets and are removed
+    /// (e.g. `1.02.003.004` becomes `1.2.3.4`).
+    ///
     /// The IP address cannot be unspecified (e.g. `0.0.0.0`).
     /// The IP port cannot be zero.
     ///
@@ -74,7 +125,7 @@ impl<'a> Address<'a> {
     /// ~~~
     pub fn new_ipv4(addr: &str, port: u16) -> Result<Self, String> {
         let addr =
-            Ipv4Addr::from_str(addr).map_err(|_| format!("invalid IP address: [{}]", addr))?;
+            parse_ipv4(addr, false).ok_or_else(|| format!("invalid IP address: [{}]", addr))?;
 
         if !addr.is_unspecified() {
             Ok(Self::IPv4(addr, NonZeroU16::new(port).ok_or("IP port cannot be zero")?))
@@ -117,7 +168,8 @@ impl<'a> Address<'a> {
 
     /// Create a new `Address::TtyDevice` from a UNIX-style tty device name.
     ///
-    /// The device name should start with `tty`.
+    /// The device name should start with `tty`, optionally with a `/dev/` prefix
+    /// (e.g. `/dev/ttyUSB0`), which is removed.
     ///
     /// # Errors
     ///
@@ -128,6 +180,11 @@ impl<'a> Address<'a> {
     /// ~~~
     /// # use ichen_openprotocol::*;
     /// assert_eq!(Err("invalid tty device: [hello]".into()), Address::new_tty_device("hello"));
+    /// assert_eq!(Err("invalid tty device: [/dev/sda1]".into()), Address::new_tty_device("/dev/sda1"));
+    /// assert_eq!(
+    ///     Err("invalid tty device: [/home/ttyS0]".into()),
+    ///     Address::new_tty_device("/home/ttyS0")
+    /// );
     /// ~~~
     ///
     /// # Examples
@@ -140,42 +197,56 @@ impl<'a> Address<'a> {
     ///     Address::TtyDevice(TextID::new("ttyHello").unwrap()),
     ///     Address::new_tty_device("ttyHello")?
     /// );
+    ///
+    /// let addr = Address::new_tty_device("/dev/ttyUSB0")?;
+    /// assert_eq!(Address::TtyDevice(TextID::new("ttyUSB0").unwrap()), addr);
+    /// assert_eq!("ttyUSB0", addr.to_string());
+    ///
+    /// // Round-trip through JSON
+    /// let json = serde_json::to_string(&addr).map_err(|err| err.to_string())?;
+    /// assert_eq!(r#""ttyUSB0""#, json);
+    /// assert_eq!(addr, serde_json::from_str::<Address>(&json).map_err(|err| err.to_string())?);
+    ///
+    /// let addr: Address = serde_json::from_str(r#""/dev/ttyS0""#).map_err(|err| err.to_string())?;
+    /// assert_eq!(Address::TtyDevice(TextID::new("ttyS0").unwrap()), addr);
     /// # Ok(())
     /// # }
     /// ~~~
     pub fn new_tty_device(device: &'a str) -> Result<Self, String> {
+        const PREFIX_DEV: &str = "/dev/";
+
         if TTY_REGEX.is_match(device) {
+            let device = device.trim_start_matches(PREFIX_DEV);
             Ok(Address::TtyDevice(device.try_into()?))
         } else {
             Err(format!("invalid tty device: [{}]", device))
         }
     }
-}
 
-impl<'a> TryFrom<&'a str> for Address<'a> {
-    type Error = String;
-
-    /// Parse a text string into an `Address`.
+    /// Parse a text string into an `Address`, rejecting leading zeros in the IP address
+    /// octets and port number.
+    ///
+    /// Some parsers treat numbers with leading zeros as octal, so `01.02.03.010` may not
+    /// mean `1.2.3.10` to them.  Use this to make sure that the address is not ambiguous.
+    ///
+    /// Otherwise this is the same as `Address::try_from`.
     ///
     /// # Errors
     ///
-    /// Returns `Err(String)` if the input string is not recognized as a valid address.
+    /// Returns `Err(String)` if the input string is not recognized as a valid address,
+    /// or if the IP address or port contains leading zeros.
     ///
     /// ## Error Examples
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
-    /// # use std::convert::TryFrom;
-    /// // The following should error because port cannot be zero if IP address is not zero
     /// assert_eq!(
-    ///     Err("IP port cannot be zero".into()),
-    ///     Address::try_from("1.02.003.004:0")
+    ///     Err("invalid IP address: [1.02.003.004]".into()),
+    ///     Address::try_from_strict("1.02.003.004:5")
     /// );
-    ///
-    /// // The following should error because port must be zero if IP address is zero
     /// assert_eq!(
-    ///     Err("null IP must have zero port number".into()),
-    ///     Address::try_from("0.0.0.0:123")
+    ///     Err("invalid IP port: [05]".into()),
+    ///     Address::try_from_strict("1.2.3.4:05")
     /// );
     /// ~~~
     ///
@@ -183,33 +254,79 @@ impl<'a> TryFrom<&'a str> for Address<'a> {
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
-    /// # use std::convert::TryFrom;
-    /// # use std::borrow::Cow;
     /// # use std::str::FromStr;
-    /// # use std::num::{NonZeroU16, NonZeroU8};
+    /// # use std::num::NonZeroU16;
     /// # use std::net::Ipv4Addr;
     /// # fn main() -> std::result::Result<(), String> {
     /// assert_eq!(
-    ///     Address::IPv4(Ipv4Addr::from_str("1.2.3.4").unwrap(), NonZeroU16::new(5).unwrap()),
-    ///     Address::try_from("1.02.003.004:05")?
+    ///     Address::IPv4(Ipv4Addr::from_str("1.2.3.40").unwrap(), NonZeroU16::new(5).unwrap()),
+    ///     Address::try_from_strict("1.2.3.40:5")?
     /// );
+    /// assert_eq!(Address::Unknown, Address::try_from_strict("0.0.0.0:0")?);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn try_from_strict(item: &'a str) -> Result<Self, String> {
+        Self::parse(item, true)
+    }
+
+    /// Get the canonical text representation of an `Address`.
     ///
-    /// // 0.0.0.0:0 is OK because both IP address and port are zero
-    /// assert_eq!(Address::Unknown, Address::try_from("0.0.0.0:0")?);
+    /// This is the same as `to_string()`, and is the format used during serialization.
     ///
-    /// assert_eq!(
-    ///     Address::ComPort(NonZeroU8::new(123).unwrap()),
-    ///     Address::try_from("COM123")?
-    /// );
+    /// Parsing an `Address` normalizes it (e.g. removing leading zeros in the IP address
+    /// octets and port number), so the canonical form may be different from the original
+    /// text string.  The canonical form always parses back into the same `Address`.
     ///
-    /// assert_eq!(
-    ///     Address::TtyDevice(TextID::new("ttyABC").unwrap()),
-    ///     Address::try_from("ttyABC")?
-    /// );
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let addr = Address::try_from("1.02.003.004:05")?;
+    /// assert_eq!("1.2.3.4:5", addr.as_canonical_string());
+    ///
+    /// // Round-trip through the canonical form
+    /// let text = addr.as_canonical_string();
+    /// assert_eq!(addr, Address::try_from(text.as_str())?);
+    /// assert_eq!(addr, Address::try_from_strict(&text)?);
+    ///
+    /// assert_eq!("COM1", Address::try_from("COM01")?.as_canonical_string());
+    /// assert_eq!("ttyS0", Address::try_from("ttyS0")?.as_canonical_string());
     /// # Ok(())
     /// # }
     /// ~~~
-    fn try_from(item: &'a str) -> std::result::Result<Self, Self::Error> {
+    pub fn as_canonical_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the transport type of the `Address`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert_eq!(Transport::Network, Address::new_ipv4("1.2.3.4", 5)?.transport());
+    /// assert_eq!(Transport::Serial, Address::new_com_port(1)?.transport());
+    /// assert_eq!(Transport::Serial, Address::new_tty_device("ttyS0")?.transport());
+    /// assert_eq!(Transport::Unknown, Address::Unknown.transport());
+    /// assert_eq!(Transport::Unknown, Address::try_from("0.0.0.0:0")?.transport());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn transport(&self) -> Transport {
+        match self {
+            Address::Unknown => Transport::Unknown,
+            Address::IPv4(_, _) => Transport::Network,
+            Address::ComPort(_) | Address::TtyDevice(_) => Transport::Serial,
+        }
+    }
+
+    /// Parse a text string into an `Address`, optionally rejecting leading zeros.
+    fn parse(item: &'a str, strict: bool) -> Result<Self, String> {
         const PREFIX_COM: &str = "COM";
 
         Ok(match item {
@@ -229,11 +346,16 @@ impl<'a> TryFrom<&'a str> for Address<'a> {
                 // Check IP address validity
                 let (address, port) = text.split_at(text.find(':').unwrap());
 
-                let address = Ipv4Addr::from_str(address).map_err(|_| "invalid IP address")?;
+                let address = parse_ipv4(address, strict)
+                    .ok_or_else(|| format!("invalid IP address: [{}]", address))?;
 
                 // Check port
                 let port = &port[1..];
 
+                if strict && has_leading_zeros(port) {
+                    return Err(format!("invalid IP port: [{}]", port));
+                }
+
                 match u16::from_str(port) {
                     // Allow port 0 on unspecified addresses only
                     Ok(0) => {
@@ -261,15 +383,111 @@ impl<'a> TryFrom<&'a str> for Address<'a> {
     }
 }
 
+impl Address<'_> {
+    /// Convert an `Address` into one that owns all its data.
+    pub(crate) fn into_owned(self) -> Address<'static> {
+        match self {
+            Address::Unknown => Address::Unknown,
+            Address::IPv4(addr, port) => Address::IPv4(addr, port),
+            Address::ComPort(port) => Address::ComPort(port),
+            Address::TtyDevice(device) => Address::TtyDevice(device.into_owned()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Address<'a> {
+    type Error = String;
+
+    /// Parse a text string into an `Address`.
+    ///
+    /// Leading zeros are allowed in the IP address octets and port number, and are removed.
+    /// Use [`Address::try_from_strict`] to reject them instead.
+    ///
+    /// [`Address::try_from_strict`]: enum.Address.html#method.try_from_strict
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the input string is not recognized as a valid address.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// // The following should error because port cannot be zero if IP address is not zero
+    /// assert_eq!(
+    ///     Err("IP port cannot be zero".into()),
+    ///     Address::try_from("1.02.003.004:0")
+    /// );
+    ///
+    /// // The following should error because port must be zero if IP address is zero
+    /// assert_eq!(
+    ///     Err("null IP must have zero port number".into()),
+    ///     Address::try_from("0.0.0.0:123")
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # use std::borrow::Cow;
+    /// # use std::str::FromStr;
+    /// # use std::num::{NonZeroU16, NonZeroU8};
+    /// # use std::net::Ipv4Addr;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert_eq!(
+    ///     Address::IPv4(Ipv4Addr::from_str("1.2.3.4").unwrap(), NonZeroU16::new(5).unwrap()),
+    ///     Address::try_from("1.02.003.004:05")?
+    /// );
+    ///
+    /// // 0.0.0.0:0 is OK because both IP address and port are zero
+    /// assert_eq!(Address::Unknown, Address::try_from("0.0.0.0:0")?);
+    ///
+    /// assert_eq!(
+    ///     Address::ComPort(NonZeroU8::new(123).unwrap()),
+    ///     Address::try_from("COM123")?
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Address::TtyDevice(TextID::new("ttyABC").unwrap()),
+    ///     Address::try_from("ttyABC")?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(item: &'a str) -> std::result::Result<Self, Self::Error> {
+        Self::parse(item, false)
+    }
+}
+
 impl Serialize for Address<'_> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // With the `null-unknown-address` feature, `Unknown` is serialized as `null`
+        #[cfg(feature = "null-unknown-address")]
+        {
+            if *self == Address::Unknown {
+                return serializer.serialize_none();
+            }
+        }
+
         Serialize::serialize(&self.to_string(), serializer)
     }
 }
 
 impl<'a, 'de: 'a> Deserialize<'de> for Address<'a> {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let s: &str = Deserialize::deserialize(deserializer)?;
-        Address::try_from(s).map_err(|err| serde::de::Error::custom(format!("{}: [{}]", err, s)))
+        // `null` is always accepted as `Unknown`
+        let s = match deserialize_optional_cow_str(deserializer)? {
+            Some(s) => s,
+            None => return Ok(Address::Unknown),
+        };
+
+        match s {
+            Cow::Borrowed(s) => Address::try_from(s),
+            Cow::Owned(ref s) => Address::try_from(s.as_str()).map(Address::into_owned),
+        }
+        .map_err(|err| serde::de::Error::custom(format!("{}: [{}]", err, s)))
     }
 }
@@ -54,7 +54,7 @@ type WebSocketClient = Client<Box<dyn NetworkStream + Send>>;
 // Pull in the `ichen_openprotocol` namespace.
 // Beware that `ichen_openprotocol::Message` will conflict with `websocket::Message`
 // so you'll need to alias on of them if you pull both into scope.
-use ichen_openprotocol::{Filters, JobCard, Message};
+use ichen_openprotocol::{Filters, JobCard, Message, TextName};
 
 struct Constants {
     users: HashMap<&'static str, (u8, String)>,
@@ -130,45 +130,42 @@ fn process_incoming_message<'a>(json: &'a str, builtin: &'a Constants) -> Option
         //
         // MIS/MES integration - User login
         // Find password in built-in list
-        Message::LoginOperator { controller_id, password, .. } => match builtin.users.get(password)
-        {
-            Some((level, name)) => {
-                println!("User found: password=[{}], access level={}.", password, level);
-
-                // Return access level
-                Some(Message::OperatorInfo {
-                    controller_id,
-                    // Cheap: Use the access level as the operator's ID
-                    operator_id: Some((u32::from(*level) + 1).try_into().unwrap()),
-                    name: name[..].try_into().unwrap(),
-                    password: password.try_into().unwrap(),
-                    level: *level,
-                    options: Default::default(),
-                })
-            }
-            None => {
-                println!("No user found with password: [{}].", password);
-
-                // Return no access
-                Some(Message::OperatorInfo {
-                    controller_id,
-                    operator_id: None,
-                    name: "Not Allowed".try_into().unwrap(),
-                    password: password.try_into().unwrap(),
-                    level: 0,
-                    options: Default::default(),
-                })
+        Message::LoginOperator { controller_id, password, .. } => {
+            match builtin.users.get(&*password) {
+                Some((level, name)) => {
+                    println!("User found: password=[{}], access level={}.", password, level);
+
+                    // Return access level
+                    Some(Message::OperatorInfo {
+                        controller_id,
+                        // Cheap: Use the access level as the operator's ID
+                        operator_id: Some((u32::from(*level) + 1).try_into().unwrap()),
+                        name: name[..].try_into().unwrap(),
+                        password: TextName::new_from_str(password).unwrap(),
+                        level: *level,
+                        options: Default::default(),
+                    })
+                }
+                None => {
+                    println!("No user found with password: [{}].", password);
+
+                    // Return no access
+                    Some(Message::OperatorInfo {
+                        controller_id,
+                        operator_id: None,
+                        name: "Not Allowed".try_into().unwrap(),
+                        password: TextName::new_from_str(password).unwrap(),
+                        level: 0,
+                        options: Default::default(),
+                    })
+                }
             }
-        },
+        }
         //
         // MIS/MES integration - request list of jobs
         Message::RequestJobCardsList { controller_id, .. } => Some(Message::JobCardsList {
             controller_id,
-            data: builtin
-                .jobs
-                .iter()
-                .map(|jc| (jc.job_card_id().try_into().unwrap(), jc.clone()))
-                .collect(), // Load jobs list
+            data: builtin.jobs.iter().cloned().map(JobCard::into_keyed).collect(), // Load jobs list
             options: Default::default(),
         }),
         //
@@ -235,7 +232,7 @@ fn run(mut client: WebSocketClient, builtin: &Constants) -> WebSocketResult<()>
                 println!("Received [{}]: {}", json.len(), json);
 
                 // Process the message, get reply message (if any)
-                if let Some(msg) = process_incoming_message(&json, &builtin) {
+                if let Some(msg) = process_incoming_message(&json, builtin) {
                     // Serialize reply message to JSON and send it to the send loop
                     match msg.to_json_str() {
                         Ok(resp) => {
@@ -341,15 +338,7 @@ fn main() {
     });
     println!("=================================================");
     println!("Built-in Job Cards for Testing:");
-    builtin.jobs.iter().for_each(|j| {
-        println!(
-            "> Name={}, Mold={}, Quantity={}/{}",
-            j.job_card_id(),
-            j.mold_id(),
-            j.progress(),
-            j.total()
-        )
-    });
+    builtin.jobs.iter().for_each(|j| println!("> {}", j));
     println!("=================================================");
 
     println!("Sending JOIN message...");
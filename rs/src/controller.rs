@@ -1,9 +1,14 @@
-use super::{Address, GeoLocation, JobMode, OpMode, Operator, TextID, TextName, ID, R32};
-use chrono::{DateTime, FixedOffset};
+use super::utils::check_f32;
+use super::{
+    validate_offline_consistency, Address, Error, GeoLocation, JobMode, KeyValuePair, Message,
+    OpMode, Operator, Result, StateValues, TextID, TextName, Timestamp, ID, R32,
+};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::borrow::Cow;
-use std::convert::TryInto;
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
 
 /// A data structure containing the current known status of a controller.
 ///
@@ -63,7 +68,7 @@ pub struct Controller<'a> {
     //
     /// Time of last connection.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub last_connection_time: Option<DateTime<FixedOffset>>,
+    pub last_connection_time: Option<Timestamp<'a>>,
     //
     /// Current logged-in user (if any) on the controller
     #[serde(flatten)]
@@ -108,6 +113,682 @@ impl Default for Controller<'_> {
     }
 }
 
+impl<'a> Controller<'a> {
+    /// Get a snapshot of the current state of the controller as a [`StateValues`].
+    ///
+    /// The `op_mode`, `job_mode`, operator ID, `job_card_id` and `mold_id` fields are copied
+    /// from the controller.  A `job_card_id` or `mold_id` that is empty or all whitespace
+    /// is not a valid state value and becomes `None`.
+    ///
+    /// [`StateValues`]: struct.StateValues.html
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::borrow::Cow;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let c = Controller {
+    ///     op_mode: OpMode::Automatic,
+    ///     job_mode: JobMode::ID02,
+    ///     operator: Some(Operator::try_new_with_name(ID::from_u32(123), "John")?),
+    ///     job_card_id: Some(Box::new(Cow::Borrowed("JC001"))),
+    ///     mold_id: Some(Box::new(Cow::Borrowed("M001"))),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let state = c.to_state_values();
+    /// assert_eq!(OpMode::Automatic, state.op_mode());
+    /// assert_eq!(JobMode::ID02, state.job_mode());
+    /// assert_eq!(Some(ID::from_u32(123)), state.operator_id());
+    /// assert_eq!(Some("JC001"), state.job_card_id());
+    /// assert_eq!(Some("M001"), state.mold_id());
+    ///
+    /// // The state is consistent with the controller
+    /// let msg = Message::ControllerStatus {
+    ///     controller_id: c.controller_id,
+    ///     display_name: None,
+    ///     is_disconnected: None,
+    ///     op_mode: None,
+    ///     job_mode: None,
+    ///     job_card_id: None,
+    ///     mold_id: None,
+    ///     operator_id: None,
+    ///     operator_name: None,
+    ///     variable: None,
+    ///     audit: None,
+    ///     alarm: None,
+    ///     state,
+    ///     controller: Some(Box::new(c.clone())),
+    ///     options: Default::default(),
+    /// };
+    /// assert_eq!(Ok(()), msg.validate());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn to_state_values(&self) -> StateValues<'_> {
+        StateValues::from_parts(
+            self.op_mode,
+            self.job_mode,
+            self.operator.as_ref().map(|user| user.id()),
+            self.job_card_id.as_ref().and_then(|jc| TextName::new_from_str(jc.as_ref().as_ref())),
+            self.mold_id.as_ref().and_then(|m| TextName::new_from_str(m.as_ref().as_ref())),
+        )
+    }
+
+    /// Compare the identity and configuration fields of two controllers, ignoring all fields
+    /// that change during operation (e.g. `op_mode`, `last_cycle_data`, `last_connection_time`).
+    ///
+    /// The fields compared are: `controller_id`, `display_name`, `controller_type`, `version`,
+    /// `model`, `address` and `geo_location`.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use chrono::DateTime;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let c1 = Controller {
+    ///     controller_id: ID::from_u32(42),
+    ///     model: TextID::new("JM138Ai").unwrap(),
+    ///     last_connection_time: Some(DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut c2 = c1.clone();
+    /// c2.last_connection_time = Some(DateTime::parse_from_rfc3339("2019-02-27T12:34:56+08:00").unwrap());
+    /// c2.op_mode = OpMode::Automatic;
+    /// c2.set_variable("Z_QDNOZTEMP", R32::new(230.0))?;
+    /// assert!(c1.config_eq(&c2));
+    ///
+    /// c2.model = TextID::new("JM168Ai").unwrap();
+    /// assert!(!c1.config_eq(&c2));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn config_eq(&self, other: &Controller<'_>) -> bool {
+        self.controller_id == other.controller_id
+            && self.display_name.get() == other.display_name.get()
+            && self.controller_type.get() == other.controller_type.get()
+            && self.version.get() == other.version.get()
+            && self.model.get() == other.model.get()
+            && self.address == other.address
+            && self.geo_location == other.geo_location
+    }
+
+    /// Compare two controllers by their display names, ignoring case.
+    ///
+    /// This is useful for sorting a list of controllers for display.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut list: Vec<Controller> = ["machine B", "Machine C", "MACHINE a"]
+    ///     .iter()
+    ///     .map(|&name| Controller { display_name: TextName::new(name).unwrap(), ..Default::default() })
+    ///     .collect();
+    ///
+    /// list.sort_by(Controller::cmp_by_display_name);
+    ///
+    /// let names: Vec<_> = list.iter().map(|c| c.display_name.get()).collect();
+    /// assert_eq!(vec!["MACHINE a", "machine B", "Machine C"], names);
+    /// ~~~
+    pub fn cmp_by_display_name(&self, other: &Controller<'_>) -> Ordering {
+        let name1 = self.display_name.chars().flat_map(char::to_lowercase);
+        let name2 = other.display_name.chars().flat_map(char::to_lowercase);
+        name1.cmp(name2)
+    }
+
+    /// Get a one-line summary of the controller, with its display name, address,
+    /// operating/job modes and logged-in operator (if any).
+    ///
+    /// The operator is shown by name, or by ID if the name is not known.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c = Controller::builder()
+    ///     .display_name("Hello")
+    ///     .address("127.0.0.1:123")
+    ///     .op_mode(OpMode::Automatic)
+    ///     .job_mode(JobMode::ID02)
+    ///     .build()?;
+    /// assert_eq!("Hello @127.0.0.1:123 [Automatic/ID02]", c.display_summary());
+    ///
+    /// c.operator = Some(Operator::new(ID::from_u32(42)));
+    /// assert_eq!("Hello @127.0.0.1:123 [Automatic/ID02] op:42", c.display_summary());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn display_summary(&self) -> String {
+        let summary =
+            format!("{} @{} [{}/{}]", self.display_name, self.address, self.op_mode, self.job_mode);
+
+        match &self.operator {
+            Some(op) => match op.name() {
+                Some(name) => format!("{} op:{}", summary, name),
+                None => format!("{} op:{}", summary, op.id()),
+            },
+            None => summary,
+        }
+    }
+
+    /// Get the last set of cycle data as an ordered list of (key, value) pairs.
+    ///
+    /// The pairs are in the same order as in `last_cycle_data`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut c: Controller = Default::default();
+    /// c.last_cycle_data.insert(TextID::new("Z_QDGODCNT").unwrap(), R32::new(123.0));
+    /// c.last_cycle_data.insert(TextID::new("Z_QDCYCTIM").unwrap(), R32::new(12.5));
+    ///
+    /// assert_eq!(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.5)], c.cycle_data_pairs());
+    /// ~~~
+    pub fn cycle_data_pairs(&self) -> Vec<(&str, f32)> {
+        self.last_cycle_data.iter().map(|(k, v)| (k.get(), v.raw())).collect()
+    }
+
+    /// Update the controller with the latest cycle data from a `CycleData` message.
+    ///
+    /// `last_cycle_data` is replaced by the message's `data`, and `op_mode` and `job_mode`
+    /// are updated from the message's `state`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError::ConstraintViolated`]`)` if the message is not a
+    /// `CycleData` message.
+    ///
+    /// Returns `Err(`[`OpenProtocolError::InconsistentField`]`)` if the message is for
+    /// a different controller.
+    ///
+    /// [`OpenProtocolError::ConstraintViolated`]: enum.OpenProtocolError.html#variant.ConstraintViolated
+    /// [`OpenProtocolError::InconsistentField`]: enum.OpenProtocolError.html#variant.InconsistentField
+    ///
+    /// ## Error Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use chrono::DateTime;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c: Controller = Default::default();
+    /// let msg = Message::new_cycle_data_simple(
+    ///     ID::from_u32(42),
+    ///     build_data_map(vec![("Z_QDGODCNT", 123.0)])?,
+    ///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+    ///     OpMode::Automatic,
+    ///     JobMode::ID02,
+    /// );
+    ///
+    /// assert_eq!(Err(OpenProtocolError::InconsistentField("controller_id")), c.merge_cycle_data(&msg));
+    /// assert_eq!(
+    ///     Err(OpenProtocolError::ConstraintViolated("Message is not CycleData.".into())),
+    ///     c.merge_cycle_data(&Message::new_alive())
+    /// );
+    /// assert!(c.last_cycle_data.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use chrono::DateTime;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c: Controller = Default::default();
+    /// c.controller_id = ID::from_u32(42);
+    /// c.set_variable("Z_QDGODCNT", R32::new(1.0))?;
+    ///
+    /// let msg = Message::new_cycle_data_simple(
+    ///     ID::from_u32(42),
+    ///     build_data_map(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.5)])?,
+    ///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+    ///     OpMode::Automatic,
+    ///     JobMode::ID02,
+    /// );
+    ///
+    /// c.merge_cycle_data(&msg).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.5)], c.cycle_data_pairs());
+    /// assert_eq!(OpMode::Automatic, c.op_mode);
+    /// assert_eq!(JobMode::ID02, c.job_mode);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn merge_cycle_data(&mut self, msg: &Message<'a>) -> Result<'a, ()> {
+        match msg {
+            Message::CycleData { controller_id, data, state, .. } => {
+                if *controller_id != self.controller_id {
+                    return Err(Error::InconsistentField("controller_id"));
+                }
+
+                self.last_cycle_data = data.clone();
+                self.op_mode = state.op_mode();
+                self.job_mode = state.job_mode();
+                Ok(())
+            }
+            _ => Err(Error::ConstraintViolated("Message is not CycleData.".into())),
+        }
+    }
+
+    /// Set the value of a variable in `variables`, adding it if it does not already exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if `name` is not a valid variable name.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut c: Controller = Default::default();
+    /// assert_eq!(
+    ///     Err("invalid value: a non-empty, non-whitespace, all-ASCII string required for variable name".into()),
+    ///     c.set_variable("  ", R32::new(1.0))
+    /// );
+    /// assert!(c.variables.is_empty());
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c: Controller = Default::default();
+    ///
+    /// c.set_variable("Z_QDNOZTEMP", R32::new(230.0))?;
+    /// c.set_variable("Z_QDTEMPZ01", R32::new(215.5))?;
+    /// assert_eq!(R32::new(230.0), c.variables["Z_QDNOZTEMP"]);
+    ///
+    /// // Update an existing variable
+    /// c.set_variable("Z_QDNOZTEMP", R32::new(235.0))?;
+    /// assert_eq!(2, c.variables.len());
+    /// assert_eq!(R32::new(235.0), c.variables["Z_QDNOZTEMP"]);
+    /// assert_eq!(R32::new(215.5), c.variables["Z_QDTEMPZ01"]);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn set_variable(&mut self, name: &str, value: R32) -> std::result::Result<(), String> {
+        let name: TextID = name.try_into().map_err(|e| format!("{} for variable name", e))?;
+        self.variables.insert(name.into_owned(), value);
+        Ok(())
+    }
+
+    /// Set the `address` of the controller from a text string.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the text string is not recognized as a valid address.
+    /// The `address` is not changed in this case.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut c: Controller = Default::default();
+    ///
+    /// assert_eq!(Err("IP port cannot be zero".into()), c.set_address("1.2.3.4:0"));
+    /// assert_eq!(Address::Unknown, c.address);
+    /// assert!(c.set_address("hello world").is_err());
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c: Controller = Default::default();
+    ///
+    /// c.set_address("192.168.1.1:123")?;
+    /// assert_eq!("192.168.1.1:123", c.address.to_string());
+    /// assert_eq!(Transport::Network, c.address.transport());
+    ///
+    /// c.set_address("COM3")?;
+    /// assert_eq!(Transport::Serial, c.address.transport());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn set_address(&mut self, address: &'a str) -> std::result::Result<(), String> {
+        self.address = address.try_into()?;
+        Ok(())
+    }
+
+    /// Set the values of a batch of variables in `variables`, in order, adding those that
+    /// do not already exist.
+    ///
+    /// To apply a [`NumericValue`] (e.g. the `variable` field of a `ControllerStatus` message),
+    /// convert it first with `R32::try_from` (which fails on loss of precision) or
+    /// [`NumericValue::to_r32_lossy`].
+    ///
+    /// [`NumericValue`]: enum.NumericValue.html
+    /// [`NumericValue::to_r32_lossy`]: enum.NumericValue.html#method.to_r32_lossy
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c: Controller = Default::default();
+    ///
+    /// c.apply_variables(&[
+    ///     KeyValuePair::new(TextID::new("Z_QDNOZTEMP").unwrap(), R32::new(230.0)),
+    ///     KeyValuePair::new(TextID::new("Z_QDTEMPZ01").unwrap(), R32::new(215.5)),
+    ///     KeyValuePair::new(TextID::new("Z_QDNOZTEMP").unwrap(), R32::new(235.0)),
+    /// ]);
+    ///
+    /// assert_eq!(2, c.variables.len());
+    /// assert_eq!(R32::new(235.0), c.variables["Z_QDNOZTEMP"]);
+    /// assert_eq!(R32::new(215.5), c.variables["Z_QDTEMPZ01"]);
+    ///
+    /// let value = NumericValue::Int(240);
+    /// c.apply_variables(&[KeyValuePair::new(TextID::new("Z_QDNOZTEMP").unwrap(), R32::try_from(value)?)]);
+    /// assert_eq!(R32::new(240.0), c.variables["Z_QDNOZTEMP"]);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn apply_variables(&mut self, pairs: &[KeyValuePair<TextID<'a>, R32>]) {
+        for pair in pairs {
+            self.variables.insert(pair.key_ref().clone(), *pair.value_ref());
+        }
+    }
+
+    /// Validate the fields of the `Controller`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if `op_mode` and `job_mode` are inconsistent (i.e. `op_mode` is
+    /// `Offline` but `job_mode` is on-line), or if any value in `last_cycle_data` or `variables`
+    /// is not a normal number.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let c = Controller { op_mode: OpMode::Offline, job_mode: JobMode::ID02, ..Default::default() };
+    /// assert_eq!(
+    ///     Err("Job mode must be Offline when op mode is Offline, not ID02.".into()),
+    ///     c.validate()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let c = Controller { op_mode: OpMode::Automatic, job_mode: JobMode::ID02, ..Default::default() };
+    /// assert_eq!(Ok(()), c.validate());
+    /// ~~~
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        validate_offline_consistency(self.op_mode, self.job_mode)?;
+
+        for (key, value) in self.last_cycle_data.iter() {
+            check_f32(value.raw()).map_err(|e| format!("{} for cycle data [{}]", e, key))?;
+        }
+
+        for (key, value) in self.variables.iter() {
+            check_f32(value.raw()).map_err(|e| format!("{} for variable [{}]", e, key))?;
+        }
+
+        Ok(())
+    }
+
+    /// Create a [`ControllerBuilder`] to build a `Controller`.
+    ///
+    /// [`ControllerBuilder`]: struct.ControllerBuilder.html
+    ///
+    pub fn builder() -> ControllerBuilder<'a> {
+        ControllerBuilder::new()
+    }
+}
+
+/// A builder for [`Controller`] with chainable setters.
+///
+/// Errors in any setter are kept until [`build`] is called, which returns the first error
+/// (if any), then validates the resulting `Controller`.
+///
+/// Fields that are not set keep their [default values].
+///
+/// [`Controller`]: struct.Controller.html
+/// [`build`]: struct.ControllerBuilder.html#method.build
+/// [default values]: struct.Controller.html#impl-Default
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # fn main() -> std::result::Result<(), String> {
+/// let c = Controller::builder()
+///     .controller_id(ID::from_u32(42))
+///     .display_name("Machine 1")
+///     .model("JM138Ai")
+///     .address("192.168.1.1:123")
+///     .geo_location(88.0, 123.0)
+///     .op_mode(OpMode::Automatic)
+///     .job_mode(JobMode::ID02)
+///     .operator(Operator::try_new_with_name(ID::from_u32(123), "John")?)
+///     .build()?;
+///
+/// assert_eq!(42, c.controller_id);
+/// assert_eq!("Machine 1", &c.display_name);
+/// assert_eq!("JM138Ai", &c.model);
+/// assert_eq!("Unknown", &c.version);
+/// assert_eq!(Some(GeoLocation::new(88.0, 123.0)?), c.geo_location);
+/// assert_eq!(Ok(()), c.validate());
+///
+/// // Errors are reported by `build`
+/// assert_eq!(
+///     Err("invalid value: a non-empty, non-whitespace, all-ASCII string required for model".into()),
+///     Controller::builder().model("").display_name("Machine 1").build().map(|_| ())
+/// );
+/// assert_eq!(
+///     Err("Job mode must be Offline when op mode is Offline, not ID02.".into()),
+///     Controller::builder().op_mode(OpMode::Offline).job_mode(JobMode::ID02).build().map(|_| ())
+/// );
+/// # Ok(())
+/// # }
+/// ~~~
+#[derive(Debug, Clone, Default)]
+pub struct ControllerBuilder<'a> {
+    controller: Controller<'a>,
+    error: Option<String>,
+}
+
+impl<'a> ControllerBuilder<'a> {
+    /// Create a new `ControllerBuilder` with all fields set to their default values.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Apply a setter, keeping the first error (if any).
+    fn apply<T>(
+        mut self,
+        value: std::result::Result<T, String>,
+        name: &str,
+        set: impl FnOnce(&mut Controller<'a>, T),
+    ) -> Self {
+        match value {
+            Ok(value) => set(&mut self.controller, value),
+            Err(e) if self.error.is_none() => self.error = Some(format!("{} for {}", e, name)),
+            Err(_) => (),
+        }
+        self
+    }
+
+    /// Set the unique ID of the controller.
+    pub fn controller_id(mut self, id: ID) -> Self {
+        self.controller.controller_id = id;
+        self
+    }
+
+    /// Set the human-friendly name of the controller.
+    pub fn display_name(self, name: &'a str) -> Self {
+        self.apply(name.try_into(), "display name", |c, v| c.display_name = v)
+    }
+
+    /// Set the controller type.
+    pub fn controller_type(self, controller_type: &'a str) -> Self {
+        self.apply(controller_type.try_into(), "controller type", |c, v| c.controller_type = v)
+    }
+
+    /// Set the version of the controller's firmware.
+    pub fn version(self, version: &'a str) -> Self {
+        self.apply(version.try_into(), "version", |c, v| c.version = v)
+    }
+
+    /// Set the machine model.
+    pub fn model(self, model: &'a str) -> Self {
+        self.apply(model.try_into(), "model", |c, v| c.model = v)
+    }
+
+    /// Set the address of the controller.
+    pub fn address(self, address: &'a str) -> Self {
+        self.apply(address.try_into(), "address", |c, v| c.address = v)
+    }
+
+    /// Set the physical geo-location of the controller.
+    pub fn geo_location(self, latitude: f32, longitude: f32) -> Self {
+        self.apply(GeoLocation::new(latitude, longitude), "geo-location", |c, v| {
+            c.geo_location = Some(v)
+        })
+    }
+
+    /// Set the current operating mode of the controller.
+    pub fn op_mode(mut self, op_mode: OpMode) -> Self {
+        self.controller.op_mode = op_mode;
+        self
+    }
+
+    /// Set the current job mode of the controller.
+    pub fn job_mode(mut self, job_mode: JobMode) -> Self {
+        self.controller.job_mode = job_mode;
+        self
+    }
+
+    /// Set the time of last connection.
+    pub fn last_connection_time(mut self, time: Timestamp<'a>) -> Self {
+        self.controller.last_connection_time = Some(time);
+        self
+    }
+
+    /// Set the current logged-in user on the controller.
+    pub fn operator(mut self, operator: Operator<'a>) -> Self {
+        self.controller.operator = Some(operator);
+        self
+    }
+
+    /// Set the active job ID on the controller.
+    pub fn job_card_id(mut self, job_card_id: &'a str) -> Self {
+        self.controller.job_card_id = Some(Box::new(Cow::Borrowed(job_card_id)));
+        self
+    }
+
+    /// Set the ID of the set of mold data currently loaded on the controller.
+    pub fn mold_id(mut self, mold_id: &'a str) -> Self {
+        self.controller.mold_id = Some(Box::new(Cow::Borrowed(mold_id)));
+        self
+    }
+
+    /// Set the value of a variable, adding it if it does not already exist.
+    pub fn variable(self, name: &'a str, value: f32) -> Self {
+        let value = TextID::try_from(name).and_then(|name| {
+            check_f32(value).map(|_| (name, R32::new(value))).map_err(String::from)
+        });
+
+        self.apply(value, "variable", |c, (name, value)| {
+            c.variables.insert(name, value);
+        })
+    }
+
+    /// Build the `Controller` and validate it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if any setter failed (the first error is returned), or if the
+    /// resulting `Controller` fails [`validate`].
+    ///
+    /// [`validate`]: struct.Controller.html#method.validate
+    ///
+    pub fn build(self) -> std::result::Result<Controller<'a>, String> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        self.controller.validate()?;
+        Ok(self.controller)
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for Controller<'a> {
+    type Error = Error<'a>;
+
+    /// Parse a JSON value (e.g. one entry in the `data` of a `ControllersList` message that
+    /// was kept as a `serde_json::Value`) into a `Controller`, then [`validate`] it.
+    ///
+    /// [`validate`]: struct.Controller.html#method.validate
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError::JsonError`]`)` if the JSON value cannot be
+    /// deserialized into a `Controller`.
+    ///
+    /// Returns `Err(`[`OpenProtocolError::ConstraintViolated`]`)` if the `Controller` fails
+    /// [`validate`] (e.g. the job mode is on-line while the op mode is `Offline`).
+    ///
+    /// [`OpenProtocolError::JsonError`]: enum.OpenProtocolError.html#variant.JsonError
+    /// [`OpenProtocolError::ConstraintViolated`]: enum.OpenProtocolError.html#variant.ConstraintViolated
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// let value = serde_json::json!({
+    ///     "controllerId": 1, "displayName": "Hello", "controllerType": "Ai02", "version": "1.0",
+    ///     "model": "JM138Ai", "IP": "127.0.0.1:123", "opMode": "Offline", "jobMode": "ID02"
+    /// });
+    ///
+    /// assert_eq!(
+    ///     Some(OpenProtocolError::ConstraintViolated(
+    ///         "Job mode must be Offline when op mode is Offline, not ID02.".into()
+    ///     )),
+    ///     Controller::try_from(&value).err()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let value: serde_json::Value = serde_json::from_str(
+    ///     r#"{"controllerId":1,"displayName":"Hello","controllerType":"Ai02","version":"1.0","model":"JM138Ai","IP":"127.0.0.1:123","opMode":"Automatic","jobMode":"ID02"}"#
+    /// ).map_err(|err| err.to_string())?;
+    ///
+    /// let c = Controller::try_from(&value).map_err(|err| err.to_string())?;
+    /// assert_eq!(1, c.controller_id);
+    /// assert_eq!("Hello", &c.display_name);
+    /// assert_eq!(OpMode::Automatic, c.op_mode);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(value: &'a Value) -> Result<'a, Self> {
+        let controller = Self::deserialize(value).map_err(Error::JsonError)?;
+        controller.validate().map_err(|err| Error::ConstraintViolated(err.into()))?;
+        Ok(controller)
+    }
+}
+
 // Tests
 
 #[cfg(test)]
@@ -115,6 +796,36 @@ mod test {
     use super::*;
     use std::result::Result;
 
+    #[test]
+    fn test_controller_null_address_from_json() -> Result<(), String> {
+        let c: Controller = serde_json::from_str(r#"{"controllerId":1,"displayName":"Hello","controllerType":"Ai02","version":"1.0","model":"JM138Ai","IP":null,"opMode":"Automatic","jobMode":"ID02"}"#).map_err(|x| x.to_string())?;
+        assert_eq!(Address::Unknown, c.address);
+        Ok(())
+    }
+
+    #[cfg(feature = "null-unknown-address")]
+    #[test]
+    fn test_controller_null_address_round_trip() -> Result<(), String> {
+        let c = Controller::builder().display_name("Hello").build()?;
+        assert_eq!(Address::Unknown, c.address);
+
+        let json = serde_json::to_string(&c).map_err(|x| x.to_string())?;
+        assert!(json.contains(r#""IP":null,"#), "{}", json);
+
+        let c2: Controller = serde_json::from_str(&json).map_err(|x| x.to_string())?;
+        assert_eq!(Address::Unknown, c2.address);
+        assert_eq!(json, serde_json::to_string(&c2).map_err(|x| x.to_string())?);
+
+        // Known addresses are not affected
+        let c = Controller::builder().address("127.0.0.1:123").build()?;
+        let json = serde_json::to_string(&c).map_err(|x| x.to_string())?;
+        assert!(json.contains(r#""IP":"127.0.0.1:123","#), "{}", json);
+
+        Ok(())
+    }
+
+    // With the `null-unknown-address` feature, the unknown address is serialized as `null`
+    #[cfg(not(feature = "null-unknown-address"))]
     #[test]
     fn test_controller_to_json() -> Result<(), String> {
         let c = Controller {
@@ -134,6 +845,29 @@ mod test {
         Ok(())
     }
 
+    // With the `null-unknown-address` feature, the unknown address is serialized as `null`
+    #[cfg(not(feature = "null-unknown-address"))]
+    #[test]
+    fn test_controller_builder_to_json() -> Result<(), String> {
+        let c = Controller::builder()
+            .op_mode(OpMode::Automatic)
+            .job_mode(JobMode::ID02)
+            .operator(Operator::try_new_with_name(ID::from_u32(123), "John")?)
+            .geo_location(88.0, 123.0)
+            .build()?;
+
+        c.validate()?;
+
+        let serialized = serde_json::to_string(&c).map_err(|x| x.to_string())?;
+
+        assert_eq!(
+            r#"{"controllerId":1,"displayName":"Unknown","controllerType":"Unknown","version":"Unknown","model":"Unknown","IP":"0.0.0.0:0","geoLatitude":88.0,"geoLongitude":123.0,"opMode":"Automatic","jobMode":"ID02","operatorId":123,"operatorName":"John"}"#,
+            serialized
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_controller_from_json() -> Result<(), String> {
         let c: Controller = serde_json::from_str(r#"{"controllerId":1,"geoLatitude":88,"geoLongitude":-123,"displayName":"Hello","controllerType":"Unknown","version":"Unknown","model":"Unknown","IP":"127.0.0.1:123","opMode":"Automatic","jobMode":"ID02","operatorId":123,"operatorName":"John"}"#).map_err(|x| x.to_string())?;
@@ -143,6 +877,8 @@ mod test {
             format!("{:?}", &c)
         );
 
+        assert_eq!("Hello @127.0.0.1:123 [Automatic/ID02] op:John", c.display_summary());
+
         Ok(())
     }
 }
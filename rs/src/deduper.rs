@@ -0,0 +1,178 @@
+use super::{Message, ID};
+use std::collections::{HashSet, VecDeque};
+
+/// A bounded buffer of recently-seen messages, for detecting duplicated messages
+/// (e.g. those re-sent by the server after a client reconnects).
+///
+/// Messages are identified by their controller ID (if any) and sequence number.
+/// Only the most recent `capacity` messages are remembered; older ones are evicted
+/// in the order they were first seen (i.e. first-in-first-out).  Unlike an LRU cache,
+/// seeing a duplicated message again does not delay its eviction.
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// let mut deduper = MessageDeduper::new(100);
+///
+/// let mut options = MessageOptions::new();
+/// options.set_sequence(1);
+/// let msg1 = Message::Alive { options: options.clone() };
+/// options.set_sequence(2);
+/// let msg2 = Message::Alive { options };
+///
+/// assert!(!deduper.seen_before(&msg1));
+/// assert!(deduper.seen_before(&msg1));
+/// assert!(!deduper.seen_before(&msg2));
+/// assert_eq!(2, deduper.len());
+/// ~~~
+#[derive(Debug, Clone)]
+pub struct MessageDeduper {
+    capacity: usize,
+    order: VecDeque<(Option<ID>, u64)>,
+    seen: HashSet<(Option<ID>, u64)>,
+}
+
+impl MessageDeduper {
+    /// Default number of recently-seen messages to remember: 1024.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Create a new `MessageDeduper` remembering at most `capacity` messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity cannot be zero");
+
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Get the maximum number of messages remembered.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of messages currently remembered.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns true if no message is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Forget all remembered messages.
+    pub fn clear(&mut self) {
+        self.order.clear();
+        self.seen.clear();
+    }
+
+    /// Check whether a message with the same controller ID (if any) and sequence number
+    /// has been seen before, remembering it if not.
+    ///
+    /// When the buffer is full, the message that was _first_ remembered is forgotten,
+    /// even if it has been seen again since.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut deduper = MessageDeduper::new(1);
+    ///
+    /// let mut options = MessageOptions::new();
+    /// options.set_sequence(1);
+    /// let msg1 = Message::Alive { options: options.clone() };
+    /// options.set_sequence(2);
+    /// let msg2 = Message::Alive { options };
+    ///
+    /// assert!(!deduper.seen_before(&msg1));
+    /// assert!(!deduper.seen_before(&msg2));     // `msg1` is evicted
+    /// assert!(!deduper.seen_before(&msg1));
+    /// assert!(deduper.seen_before(&msg1));
+    /// ~~~
+    pub fn seen_before(&mut self, message: &Message<'_>) -> bool {
+        let key = (message.controller_id(), message.sequence());
+
+        if self.seen.contains(&key) {
+            return true;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.order.push_back(key);
+        self.seen.insert(key);
+        false
+    }
+}
+
+impl Default for MessageDeduper {
+    /// Default value for `MessageDeduper`, remembering at most
+    /// [`DEFAULT_CAPACITY`](#associatedconstant.DEFAULT_CAPACITY) messages.
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MessageOptions;
+    use std::result::Result;
+
+    #[test]
+    fn test_deduper_cycle_data() -> Result<(), String> {
+        let mut deduper = MessageDeduper::default();
+
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":123,"data":{"Z_QDGODCNT":123},"sequence":42}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert!(!deduper.seen_before(&msg));
+
+        // Duplicated message after a resync
+        let dup = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert!(deduper.seen_before(&dup));
+
+        // Same sequence number from a different controller is novel
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":456,"data":{"Z_QDGODCNT":123},"sequence":42}"#;
+        let novel = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert!(!deduper.seen_before(&novel));
+
+        assert_eq!(2, deduper.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deduper_evicts_first_seen() {
+        let msgs: Vec<_> = (1..=3)
+            .map(|seq| {
+                let mut options = MessageOptions::new();
+                options.set_sequence(seq);
+                Message::Alive { options }
+            })
+            .collect();
+
+        let mut deduper = MessageDeduper::new(2);
+        assert!(!deduper.seen_before(&msgs[0]));
+        assert!(!deduper.seen_before(&msgs[1]));
+
+        // A duplicate does not refresh the entry, so it is still evicted first
+        assert!(deduper.seen_before(&msgs[0]));
+        assert!(!deduper.seen_before(&msgs[2]));
+
+        assert!(deduper.seen_before(&msgs[1]));
+        assert!(!deduper.seen_before(&msgs[0]));
+        assert_eq!(2, deduper.len());
+    }
+}
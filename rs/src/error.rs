@@ -8,18 +8,18 @@ pub enum OpenProtocolError<'a> {
     /// The value of a field is the empty string `""` or containing all white-spaces,
     /// which is not allowed as value of that field.
     #[display(fmt = "field {} cannot be empty or all whitespace", _0)]
-    EmptyField(&'a str),
+    EmptyField(&'static str),
     //
     /// The value of a field is not valid.
     #[display(fmt = "value [{}] is invalid for the field {} - {}", value, field, description)]
-    InvalidField { field: &'a str, value: Cow<'a, str>, description: Cow<'a, str> },
+    InvalidField { field: &'static str, value: Cow<'a, str>, description: Cow<'a, str> },
     //
     /// The value of a field is not consistent with the matching value in the [`state`].
     ///
     /// [`state`]: struct.StateValues.html
     ///
     #[display(fmt = "value of field {} is not the same as the matching field in the state", _0)]
-    InconsistentState(&'a str),
+    InconsistentState(&'static str),
     //
     /// The value of a field is not consistent with the matching value in the
     /// [`Controller`] structure.
@@ -29,7 +29,7 @@ pub enum OpenProtocolError<'a> {
         fmt = "value of field {} is not the same as the matching field in the Controller",
         _0
     )]
-    InconsistentField(&'a str),
+    InconsistentField(&'static str),
     //
     /// An enforced constraint is broken.
     #[display(fmt = "{}", _0)]
@@ -44,10 +44,88 @@ pub enum OpenProtocolError<'a> {
     SystemError(Cow<'a, str>),
 }
 
+impl OpenProtocolError<'_> {
+    /// Convert an `OpenProtocolError` into one that owns all its data and does not borrow
+    /// from the input (e.g. the original JSON string).
+    ///
+    /// This is useful when the error must out-live the input.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::borrow::Cow;
+    /// fn check(value: &str) -> std::result::Result<(), OpenProtocolError<'_>> {
+    ///     Err(OpenProtocolError::InvalidField {
+    ///         field: "name",
+    ///         value: Cow::Borrowed(value),
+    ///         description: "invalid name".into(),
+    ///     })
+    /// }
+    ///
+    /// let err: OpenProtocolError<'static> = {
+    ///     let input = String::from("hello");
+    ///     let err = check(&input).unwrap_err();
+    ///     err.into_owned()
+    ///     // `input` is dropped here
+    /// };
+    ///
+    /// assert_eq!("value [hello] is invalid for the field name - invalid name", err.to_string());
+    /// ~~~
+    pub fn into_owned(self) -> OpenProtocolError<'static> {
+        match self {
+            Self::EmptyField(field) => OpenProtocolError::EmptyField(field),
+            Self::InvalidField { field, value, description } => OpenProtocolError::InvalidField {
+                field,
+                value: Cow::Owned(value.into_owned()),
+                description: Cow::Owned(description.into_owned()),
+            },
+            Self::InconsistentState(field) => OpenProtocolError::InconsistentState(field),
+            Self::InconsistentField(field) => OpenProtocolError::InconsistentField(field),
+            Self::ConstraintViolated(err) => {
+                OpenProtocolError::ConstraintViolated(Cow::Owned(err.into_owned()))
+            }
+            Self::JsonError(err) => OpenProtocolError::JsonError(err),
+            Self::SystemError(err) => OpenProtocolError::SystemError(Cow::Owned(err.into_owned())),
+        }
+    }
+
+    /// Get the location (line, column) in the JSON text where a [`JsonError`] occurred.
+    ///
+    /// Both the line and column numbers start from 1.
+    ///
+    /// Returns `None` if the error is not a [`JsonError`], or if the location is not available
+    /// (e.g. the error occurred while serializing).
+    ///
+    /// [`JsonError`]: #variant.JsonError
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let json = "{\n  \"$type\": \"Alive\",\n  \"sequence\": 1,,\n}";
+    /// let err = Message::parse_from_json_str(json).unwrap_err();
+    /// assert_eq!(Some((3, 17)), err.json_location());
+    ///
+    /// let err = Message::parse_from_json_str(r#"{"$type":"Alive","sequence":1"#).unwrap_err();
+    /// assert_eq!(Some((1, 29)), err.json_location());
+    ///
+    /// assert_eq!(None, OpenProtocolError::EmptyField("hello").json_location());
+    /// ~~~
+    pub fn json_location(&self) -> Option<(usize, usize)> {
+        match self {
+            // serde_json reports line 0 when the location is not available
+            Self::JsonError(err) if err.line() > 0 => Some((err.line(), err.column())),
+            _ => None,
+        }
+    }
+}
+
 impl std::error::Error for OpenProtocolError<'_> {
     fn description(&self) -> &str {
         match self {
             // JSON error
+            #[allow(deprecated)]
             Self::JsonError(err) => err.description(),
             //
             // Invalid field value
@@ -86,6 +164,32 @@ impl std::error::Error for OpenProtocolError<'_> {
             _ => None,
         }
     }
+
+    /// Get the underlying cause of the error.
+    ///
+    /// Only [`JsonError`] has an underlying cause, which is the `serde_json::Error`.
+    ///
+    /// [`JsonError`]: #variant.JsonError
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// use std::error::Error;
+    ///
+    /// let err = Message::parse_from_json_str(r#"{"$type":"Alive""#).unwrap_err();
+    /// assert!(err.source().is_some());
+    /// assert!(err.source().unwrap().is::<serde_json::Error>());
+    ///
+    /// let err = OpenProtocolError::EmptyField("hello");
+    /// assert!(err.source().is_none());
+    /// ~~~
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::JsonError(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for OpenProtocolError<'_> {
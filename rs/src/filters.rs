@@ -1,5 +1,6 @@
 #![allow(non_upper_case_globals)]
 
+use super::utils::deserialize_cow_str;
 use bitflags::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
@@ -13,6 +14,36 @@ bitflags! {
     ///
     /// [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/doc/enums.md#filters
     ///
+    /// # Examples
+    ///
+    /// Filters can be combined with `+` or collected from an iterator of filters.
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let f1 = Filters::Status + Filters::Cycle + Filters::JobCards;
+    /// let f2: Filters = vec![Filters::Status, Filters::Cycle, Filters::JobCards].into_iter().collect();
+    /// assert_eq!(f1, f2);
+    ///
+    /// let f: Filters = Vec::new().into_iter().collect();
+    /// assert_eq!(Filters::None, f);
+    /// ~~~
+    ///
+    /// The MIS/MES integration filters (`JobCards` = `0x1000`, `Operators` = `0x2000`) and
+    /// the industrial bus integration filter (`OPCUA` = `0x1000_0000`) are independent bits,
+    /// separate from the controller-related filters included in `All` (`0xFF`).
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let bits = [Filters::JobCards, Filters::Operators, Filters::OPCUA];
+    /// assert_eq!(vec![0x1000, 0x2000, 0x1000_0000], bits.iter().map(|f| f.bits()).collect::<Vec<_>>());
+    ///
+    /// for (i, f) in bits.iter().enumerate() {
+    ///     assert_eq!(1, f.bits().count_ones());
+    ///     assert!(!f.intersects(Filters::All));
+    ///     assert!(bits.iter().skip(i + 1).all(|&other| !f.intersects(other)));
+    /// }
+    /// ~~~
+    ///
     pub struct Filters: u32 {
         /// No rights.
         const None = 0;
@@ -46,6 +77,19 @@ bitflags! {
 
 static ALL: &str = "Status | Cycle | Mold | Actions | Alarms | Audit | All";
 
+/// All single-bit filters, in bit order.
+static SINGLE: [Filters; 9] = [
+    Filters::Status,
+    Filters::Cycle,
+    Filters::Mold,
+    Filters::Actions,
+    Filters::Alarms,
+    Filters::Audit,
+    Filters::JobCards,
+    Filters::Operators,
+    Filters::OPCUA,
+];
+
 impl Filters {
     /// Is a particular set of filters set?
     ///
@@ -63,6 +107,276 @@ impl Filters {
     pub fn has(self, other: Self) -> bool {
         self.contains(other)
     }
+
+    /// Get the raw integer bitmask of the filters value.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(0, Filters::None.bits_value());
+    /// assert_eq!(0b_0000_0011, (Filters::Status + Filters::Cycle).bits_value());
+    /// assert_eq!(0b_0001_0000_1111_1111, (Filters::All + Filters::JobCards).bits_value());
+    /// ~~~
+    pub fn bits_value(self) -> u32 {
+        self.bits()
+    }
+
+    /// Get the number of distinct filters set.
+    ///
+    /// `All` counts as its six component filters (`Status`, `Cycle`, `Mold`, `Actions`,
+    /// `Alarms` and `Audit`).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(0, Filters::None.count());
+    /// assert_eq!(2, (Filters::Status + Filters::Cycle).count());
+    /// assert_eq!(6, Filters::All.count());
+    /// assert_eq!(8, (Filters::All + Filters::Mold + Filters::JobCards + Filters::OPCUA).count());
+    /// ~~~
+    pub fn count(self) -> u32 {
+        self.iter().count() as u32
+    }
+
+    /// Get an iterator over the distinct single-bit filters set, in bit order.
+    ///
+    /// `All` yields its six component filters (`Status`, `Cycle`, `Mold`, `Actions`,
+    /// `Alarms` and `Audit`).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let f = Filters::OPCUA + Filters::Mold + Filters::Status;
+    /// let list: Vec<_> = f.iter().collect();
+    /// assert_eq!(vec![Filters::Status, Filters::Mold, Filters::OPCUA], list);
+    ///
+    /// assert_eq!(0, Filters::None.iter().count());
+    /// ~~~
+    pub fn iter(self) -> FiltersIter {
+        FiltersIter { filters: self, index: 0 }
+    }
+
+    /// Get the canonical form of the filters value.
+    ///
+    /// `All` is `0xFF`, which is a superset of its six component filters (`Status`, `Cycle`,
+    /// `Mold`, `Actions`, `Alarms` and `Audit`, together `0x3F`).  The two extra bits in `All`
+    /// carry no meaning by themselves.
+    ///
+    /// In the canonical form:
+    ///
+    /// * if all six component filters are set, `All` is also set (so the six components
+    ///   together equal `All`),
+    /// * otherwise, the extra bits of `All` are cleared.
+    ///
+    /// Other filters (e.g. `JobCards`, `OPCUA`) are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let f = Filters::Status + Filters::Cycle + Filters::Mold
+    ///             + Filters::Actions + Filters::Alarms + Filters::Audit;
+    ///
+    /// // The raw bits are different...
+    /// assert_eq!(0x3F, f.bits_value());
+    /// assert_eq!(0xFF, Filters::All.bits_value());
+    /// assert_ne!(Filters::All, f);
+    ///
+    /// // ...but the canonical forms are the same
+    /// assert_eq!(Filters::All, f.canonicalize());
+    /// assert_eq!(Filters::All, Filters::All.canonicalize());
+    /// assert_eq!("All", f.canonicalize().to_string());
+    ///
+    /// assert_eq!(Filters::All + Filters::OPCUA, (f + Filters::OPCUA).canonicalize());
+    /// assert_eq!(Filters::Status + Filters::Mold, (Filters::Status + Filters::Mold).canonicalize());
+    ///
+    /// // Extra bits without all six components are cleared
+    /// let f = Filters::from_bits(0b_1100_0001).unwrap();
+    /// assert_eq!(Filters::Status, f.canonicalize());
+    /// ~~~
+    pub fn canonicalize(self) -> Self {
+        let components = Filters::Status
+            | Filters::Cycle
+            | Filters::Mold
+            | Filters::Actions
+            | Filters::Alarms
+            | Filters::Audit;
+
+        if self.contains(components) {
+            self | Filters::All
+        } else {
+            self - (Filters::All - components)
+        }
+    }
+
+    /// Create a filters value from a raw integer bitmask.
+    ///
+    /// Unlike `from_bits`, the extra bits of `All` (`0x40` and `0x80`) are only accepted
+    /// together with all the other bits of `All` (i.e. `0xFF`), because they do not
+    /// correspond to any single filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `None` if `bits` contains any bit that does not correspond to a single filter,
+    /// other than as part of `All`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let f = Filters::Status + Filters::Mold + Filters::Operators;
+    /// assert_eq!(Some(f), Filters::from_bits_checked(f.bits_value()));
+    /// assert_eq!(Some(Filters::None), Filters::from_bits_checked(0));
+    /// assert_eq!(Some(Filters::All + Filters::OPCUA), Filters::from_bits_checked(0x1000_00FF));
+    /// assert_eq!(None, Filters::from_bits_checked(0b_0100_0000_0000));
+    ///
+    /// // Bits of `All` that are not single filters
+    /// assert_eq!(None, Filters::from_bits_checked(0x40));
+    /// assert_eq!(None, Filters::from_bits_checked(0x81));
+    /// ~~~
+    pub fn from_bits_checked(bits: u32) -> Option<Self> {
+        let f = Self::from_bits(bits)?;
+        let singles = SINGLE.iter().fold(Filters::None, |acc, &f| acc | f);
+
+        if f.contains(Filters::All) || singles.contains(f) {
+            Some(f)
+        } else {
+            None
+        }
+    }
+
+    /// Serialize `Filters` as an integer bitmask instead of the default comma-separated list.
+    ///
+    /// Use this with `#[serde(serialize_with = "Filters::to_json_bits")]`
+    /// (and the matching [`from_json_bits`]) on a field to store the integer form.
+    ///
+    /// [`from_json_bits`]: #method.from_json_bits
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use serde::{Deserialize, Serialize};
+    /// # fn main() -> std::result::Result<(), serde_json::Error> {
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Record {
+    ///     #[serde(serialize_with = "Filters::to_json_bits")]
+    ///     #[serde(deserialize_with = "Filters::from_json_bits")]
+    ///     filter: Filters,
+    /// }
+    ///
+    /// let rec = Record { filter: Filters::Cycle + Filters::JobCards };
+    /// let json = serde_json::to_string(&rec)?;
+    /// assert_eq!(r#"{"filter":4098}"#, json);
+    ///
+    /// let rec: Record = serde_json::from_str(&json)?;
+    /// assert_eq!(Filters::Cycle + Filters::JobCards, rec.filter);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn to_json_bits<S: Serializer>(filters: &Self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u32(filters.bits())
+    }
+
+    /// Deserialize `Filters` from an integer bitmask.
+    ///
+    /// Use this with `#[serde(deserialize_with = "Filters::from_json_bits")]`
+    /// (and the matching [`to_json_bits`]) on a field to read the integer form.
+    ///
+    /// [`to_json_bits`]: #method.to_json_bits
+    ///
+    /// # Errors
+    ///
+    /// Fails if the bitmask contains any bit that does not correspond to a filter.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let f = Filters::from_json_bits(serde_json::json!(4098)).unwrap();
+    /// assert_eq!(Filters::Cycle + Filters::JobCards, f);
+    /// assert!(Filters::from_json_bits(serde_json::json!(1024)).is_err());
+    /// ~~~
+    pub fn from_json_bits<'de, D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let bits: u32 = Deserialize::deserialize(d)?;
+        Self::from_bits_checked(bits).ok_or_else(|| {
+            serde::de::Error::custom(format!("invalid filters bitmask: {:#x}", bits))
+        })
+    }
+}
+
+/// An iterator over the distinct single-bit filters set in a [`Filters`] value.
+///
+/// This is created by [`Filters::iter`].
+///
+/// [`Filters`]: struct.Filters.html
+/// [`Filters::iter`]: struct.Filters.html#method.iter
+///
+#[derive(Debug, Clone)]
+pub struct FiltersIter {
+    filters: Filters,
+    index: usize,
+}
+
+impl Iterator for FiltersIter {
+    type Item = Filters;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&f) = SINGLE.get(self.index) {
+            self.index += 1;
+
+            if self.filters.contains(f) {
+                return Some(f);
+            }
+        }
+
+        None
+    }
+}
+
+impl IntoIterator for Filters {
+    type Item = Filters;
+    type IntoIter = FiltersIter;
+
+    /// Get an iterator over the distinct single-bit filters set.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut list = Vec::new();
+    ///
+    /// for f in Filters::All + Filters::OPCUA {
+    ///     list.push(f);
+    /// }
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         Filters::Status,
+    ///         Filters::Cycle,
+    ///         Filters::Mold,
+    ///         Filters::Actions,
+    ///         Filters::Alarms,
+    ///         Filters::Audit,
+    ///         Filters::OPCUA
+    ///     ],
+    ///     list
+    /// );
+    ///
+    /// // Collect the filters back into a `Filters` value
+    /// let f = Filters::All + Filters::OPCUA;
+    /// let f2: Filters = f.into_iter().collect();
+    /// assert_eq!(Filters::Status + Filters::Cycle + Filters::Mold + Filters::Actions
+    ///                 + Filters::Alarms + Filters::Audit + Filters::OPCUA, f2);
+    /// assert_eq!(f, f2.canonicalize());
+    /// ~~~
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl FromStr for Filters {
@@ -164,6 +478,7 @@ impl AddAssign for Filters {
     /// f += Filters::All;
     /// assert_eq!(Filters::All + Filters::OPCUA, f);
     /// ~~~
+    #[allow(clippy::suspicious_op_assign_impl)]
     fn add_assign(&mut self, other: Self) {
         *self |= other;
     }
@@ -205,7 +520,7 @@ impl Serialize for Filters {
 
 impl<'de> Deserialize<'de> for Filters {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
-        let s = Deserialize::deserialize(d).map_err(serde::de::Error::custom)?;
-        Filters::from_str(s).map_err(serde::de::Error::custom)
+        let s = deserialize_cow_str(d)?;
+        Filters::from_str(&s).map_err(serde::de::Error::custom)
     }
 }
@@ -56,6 +56,128 @@ impl GeoLocation {
         self.geo_longitude.into()
     }
 
+    /// Is this `GeoLocation` within a bounding box (inclusive)?
+    ///
+    /// The bounding box is specified by its south-west corner `sw` and north-east corner `ne`.
+    ///
+    /// If `ne` has a longitude smaller than `sw`, the bounding box is taken to span
+    /// the anti-meridian (i.e. longitude 180).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let sw = GeoLocation::new(20.0, 100.0)?;
+    /// let ne = GeoLocation::new(40.0, 120.0)?;
+    ///
+    /// assert!(GeoLocation::new(30.0, 110.0)?.within(sw, ne));
+    /// assert!(GeoLocation::new(20.0, 120.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(10.0, 110.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(30.0, 130.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(30.0, -110.0)?.within(sw, ne));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// // A bounding box spanning the anti-meridian
+    /// let sw = GeoLocation::new(-20.0, 170.0)?;
+    /// let ne = GeoLocation::new(0.0, -170.0)?;
+    ///
+    /// assert!(GeoLocation::new(-10.0, 175.0)?.within(sw, ne));
+    /// assert!(GeoLocation::new(-10.0, 180.0)?.within(sw, ne));
+    /// assert!(GeoLocation::new(-10.0, -180.0)?.within(sw, ne));
+    /// assert!(GeoLocation::new(-10.0, -175.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(-10.0, 0.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(-10.0, 160.0)?.within(sw, ne));
+    /// assert!(!GeoLocation::new(10.0, 175.0)?.within(sw, ne));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn within(self, sw: GeoLocation, ne: GeoLocation) -> bool {
+        let (lat, lng) = (self.geo_latitude, self.geo_longitude);
+
+        if lat < sw.geo_latitude || lat > ne.geo_latitude {
+            return false;
+        }
+
+        if sw.geo_longitude <= ne.geo_longitude {
+            lng >= sw.geo_longitude && lng <= ne.geo_longitude
+        } else {
+            // Spans the anti-meridian
+            lng >= sw.geo_longitude || lng <= ne.geo_longitude
+        }
+    }
+
+    /// Round the latitude and longitude to a number of decimal places.
+    ///
+    /// This is useful for removing noise (e.g. from GPS readings) before serialization.
+    /// Since `f32` has about 7 significant digits, `decimals` larger than 7 has no effect.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let geo = GeoLocation::new(22.283_456_7, 114.158_923)?.rounded(4);
+    /// assert_eq!(22.2835, geo.latitude());
+    /// assert_eq!(114.1589, geo.longitude());
+    ///
+    /// let json = serde_json::to_string(&geo).map_err(|err| err.to_string())?;
+    /// assert_eq!(r#"{"geoLatitude":22.2835,"geoLongitude":114.1589}"#, json);
+    ///
+    /// assert_eq!(GeoLocation::new(90.0, -180.0)?, GeoLocation::new(89.999_99, -179.999_99)?.rounded(2));
+    /// assert_eq!(GeoLocation::new(-12.0, 99.0)?, GeoLocation::new(-12.3, 98.5)?.rounded(0));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn rounded(self, decimals: u32) -> GeoLocation {
+        if decimals > 7 {
+            return self;
+        }
+
+        let scale = 10_f64.powi(decimals as i32);
+        let round = |value: f32| ((f64::from(value) * scale).round() / scale) as f32;
+
+        // Rounding cannot move a value outside of the valid range
+        Self::new(round(self.latitude()), round(self.longitude())).unwrap()
+    }
+
+    /// Convert the `GeoLocation` into a [GeoJSON] `Point` geometry.
+    ///
+    /// Notice that GeoJSON coordinates are in (longitude, latitude) order.
+    ///
+    /// [GeoJSON]: https://tools.ietf.org/html/rfc7946
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let geo = GeoLocation::new(22.283, 114.159)?;
+    /// let point = geo.to_geojson();
+    ///
+    /// assert_eq!("Point", point["type"]);
+    /// assert_eq!(114.159, point["coordinates"][0]);
+    /// assert_eq!(22.283, point["coordinates"][1]);
+    /// assert_eq!(r#"{"coordinates":[114.159,22.283],"type":"Point"}"#, point.to_string());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn to_geojson(self) -> serde_json::Value {
+        // Go through the shortest decimal representation so the `f32` values do not
+        // pick up noise digits when widened to `f64` (e.g. `22.283` -> `22.283000946044922`)
+        let widen = |value: f32| value.to_string().parse::<f64>().unwrap();
+
+        serde_json::json!({
+            "type": "Point",
+            "coordinates": [widen(self.longitude()), widen(self.latitude())]
+        })
+    }
+
     /// Create a new `GeoLocation`.
     ///
     /// # Errors
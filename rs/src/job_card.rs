@@ -1,10 +1,26 @@
-use super::TextName;
+use super::{Error, Result, TextName};
+use derive_more::*;
 use serde::{Deserialize, Serialize};
-use std::convert::TryInto;
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
 
 /// A data structure containing information on a production job (i.e. a *job card*).
 ///
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+/// It displays as a concise summary in the format `job_card_id [mold_id] progress/total`.
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # fn main() -> std::result::Result<(), String> {
+/// let jc = JobCard::try_new("J001", "Mold#001", 100, 1000)?;
+/// assert_eq!("J001 [Mold#001] 100/1000", jc.to_string());
+/// # Ok(())
+/// # }
+/// ~~~
+///
+#[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[display(fmt = "{} [{}] {}/{}", job_card_id, mold_id, progress, total)]
 #[serde(rename_all = "camelCase")]
 pub struct JobCard<'a> {
     /// Unique job ID, which must not be empty or all white-spaces.
@@ -55,6 +71,29 @@ impl<'a> JobCard<'a> {
         self.mold_id.as_ref()
     }
 
+    /// Returns true if the mold ID starts with `prefix` (case-sensitive).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let cards = vec![
+    ///     JobCard::try_new("JOB_CARD_1", "ABC-123", 0, 8000)?,
+    ///     JobCard::try_new("JOB_CARD_2", "M002", 2000, 10000)?,
+    ///     JobCard::try_new("JOB_CARD_3", "MOULD_003", 888, 3333)?,
+    ///     JobCard::try_new("JOB_CARD_4", "MOULD_004", 123, 45678)?,
+    /// ];
+    ///
+    /// let ids: Vec<_> = cards.iter().filter(|jc| jc.mold_matches_prefix("MOULD_")).map(|jc| jc.job_card_id()).collect();
+    /// assert_eq!(vec!["JOB_CARD_3", "JOB_CARD_4"], ids);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn mold_matches_prefix(&self, prefix: &str) -> bool {
+        self.mold_id.matches_prefix(prefix)
+    }
+
     /// Get the production progress.
     ///
     /// # Examples
@@ -87,6 +126,188 @@ impl<'a> JobCard<'a> {
         self.total
     }
 
+    /// Get the production progress as a percentage (0-100) of the total production count.
+    ///
+    /// Returns 0.0 if `total` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert_eq!(10.0, JobCard::try_new("J001", "Mold#001", 100, 1000)?.percent_complete());
+    /// assert_eq!(0.0, JobCard::try_new("J002", "Mold#002", 0, 5000)?.percent_complete());
+    /// assert_eq!(100.0, JobCard::try_new("J003", "Mold#003", 42, 42)?.percent_complete());
+    /// assert_eq!(0.0, JobCard::try_new("J004", "Mold#004", 0, 0)?.percent_complete());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn percent_complete(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            f64::from(self.progress) / f64::from(self.total) * 100.0
+        }
+    }
+
+    /// Returns true if the production progress has reached the total production count.
+    ///
+    /// A job card with a `total` of zero is never complete.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert!(!JobCard::try_new("J001", "Mold#001", 100, 1000)?.is_complete());
+    /// assert!(JobCard::try_new("J003", "Mold#003", 42, 42)?.is_complete());
+    /// assert!(!JobCard::try_new("J004", "Mold#004", 0, 0)?.is_complete());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.progress >= self.total
+    }
+
+    /// Get the total remaining production count (i.e. `total` - `progress`) across
+    /// a list of job cards.
+    ///
+    /// Job cards with `progress` larger than `total` count as zero remaining.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let cards = vec![
+    ///     JobCard::try_new("JOB_CARD_1", "ABC-123", 0, 8000)?,
+    ///     JobCard::try_new("JOB_CARD_2", "M002", 2000, 10000)?,
+    ///     JobCard::try_new("JOB_CARD_3", "MOULD_003", 888, 3333)?,
+    ///     JobCard::try_new("JOB_CARD_4", "MOULD_004", 123, 45678)?,
+    /// ];
+    /// assert_eq!(8000 + 8000 + 2445 + 45555, JobCard::total_remaining(&cards));
+    /// assert_eq!(0, JobCard::total_remaining(&[]));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn total_remaining(cards: &[JobCard<'_>]) -> u64 {
+        cards
+            .iter()
+            .map(|jc| u64::from(jc.total.saturating_sub(jc.progress)))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Convert a `JobCard` into a (key, value) pair keyed by its job ID, suitable for
+    /// collecting into the `data` dictionary of a `JobCardsList` message.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let jobs = vec![
+    ///     JobCard::try_new("J001", "Mold#001", 0, 10000)?,
+    ///     JobCard::try_new("J002", "Mold#002", 1000, 5000)?,
+    /// ];
+    ///
+    /// let msg = Message::JobCardsList {
+    ///     controller_id: ID::from_u32(123),
+    ///     data: jobs.into_iter().map(JobCard::into_keyed).collect(),
+    ///     options: Default::default(),
+    /// };
+    ///
+    /// if let Message::JobCardsList { data, .. } = &msg {
+    ///     assert_eq!(2, data.len());
+    ///     assert_eq!("Mold#001", data["J001"].mold_id());
+    ///     assert_eq!(5000, data["J002"].total());
+    ///     assert_eq!(vec!["J001", "J002"], data.keys().map(|k| k.get()).collect::<Vec<_>>());
+    /// } else {
+    ///     panic!();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn into_keyed(self) -> (TextName<'a>, Self) {
+        (self.job_card_id.clone(), self)
+    }
+
+    /// Set the production progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if `progress` is larger than `total`.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut jc = JobCard::try_new("J001", "Mold#001", 100, 1000)?;
+    /// assert_eq!(Err("progress cannot be larger than total".into()), jc.set_progress(1001));
+    /// assert_eq!(100, jc.progress());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut jc = JobCard::try_new("J001", "Mold#001", 100, 1000)?;
+    /// jc.set_progress(1000)?;
+    /// assert_eq!(1000, jc.progress());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn set_progress(&mut self, progress: u32) -> std::result::Result<(), String> {
+        if progress > self.total {
+            return Err("progress cannot be larger than total".into());
+        }
+
+        self.progress = progress;
+        Ok(())
+    }
+
+    /// Advance the production progress by a number of pieces.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the new progress would be larger than `total`.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut jc = JobCard::try_new("J001", "Mold#001", 990, 1000)?;
+    /// assert_eq!(Err("progress cannot be larger than total".into()), jc.increment_progress(11));
+    /// assert_eq!(Err("progress cannot be larger than total".into()), jc.increment_progress(u32::MAX));
+    /// assert_eq!(990, jc.progress());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut jc = JobCard::try_new("J001", "Mold#001", 990, 1000)?;
+    /// jc.increment_progress(1)?;
+    /// assert_eq!(991, jc.progress());
+    /// jc.increment_progress(9)?;
+    /// assert_eq!(1000, jc.progress());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn increment_progress(&mut self, by: u32) -> std::result::Result<(), String> {
+        match self.progress.checked_add(by) {
+            Some(progress) => self.set_progress(progress),
+            None => Err("progress cannot be larger than total".into()),
+        }
+    }
+
     /// Create a new `JobCard` with the specified field values.
     ///
     /// # Errors
@@ -149,3 +370,68 @@ impl<'a> JobCard<'a> {
         })
     }
 }
+
+impl<'a> TryFrom<&'a Value> for JobCard<'a> {
+    type Error = Error<'a>;
+
+    /// Parse a JSON value (e.g. one item in a list of jobs within a larger document) into
+    /// a `JobCard`.
+    ///
+    /// As with [`try_new`], the production `progress` cannot be larger than the `total`.
+    ///
+    /// [`try_new`]: struct.JobCard.html#method.try_new
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError::JsonError`]`)` if the JSON value cannot be
+    /// deserialized into a `JobCard` (e.g. the job card ID is empty).
+    ///
+    /// Returns `Err(`[`OpenProtocolError::ConstraintViolated`]`)` if `progress` is larger
+    /// than `total`.
+    ///
+    /// [`OpenProtocolError::JsonError`]: enum.OpenProtocolError.html#variant.JsonError
+    /// [`OpenProtocolError::ConstraintViolated`]: enum.OpenProtocolError.html#variant.ConstraintViolated
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// let value = serde_json::json!({ "jobCardId": "JOB01", "moldId": "ABC-123", "progress": 9000, "total": 8000 });
+    ///
+    /// assert_eq!(
+    ///     Err(OpenProtocolError::ConstraintViolated("progress cannot be larger than total".into())),
+    ///     JobCard::try_from(&value)
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let doc: serde_json::Value = serde_json::from_str(
+    ///     r#"{"site":"HK","jobs":[{"jobCardId":"JOB01","moldId":"ABC-123","progress":100,"total":8000}]}"#
+    /// ).map_err(|err| err.to_string())?;
+    ///
+    /// let jc = JobCard::try_from(&doc["jobs"][0]).map_err(|err| err.to_string())?;
+    /// assert_eq!("JOB01", jc.job_card_id());
+    /// assert_eq!("ABC-123", jc.mold_id());
+    /// assert_eq!(100, jc.progress());
+    /// assert_eq!(8000, jc.total());
+    ///
+    /// assert!(JobCard::try_from(&doc["site"]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(value: &'a Value) -> Result<'a, Self> {
+        let jc = Self::deserialize(value).map_err(Error::JsonError)?;
+
+        if jc.progress > jc.total {
+            return Err(Error::ConstraintViolated("progress cannot be larger than total".into()));
+        }
+
+        Ok(jc)
+    }
+}
@@ -80,3 +80,47 @@ impl<K, V> KeyValuePair<K, V> {
         Self { key, value }
     }
 }
+
+impl<K, V> From<(K, V)> for KeyValuePair<K, V> {
+    /// Create a `KeyValuePair` from a `(key, value)` tuple.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use indexmap::IndexMap;
+    /// let kv: KeyValuePair<_, _> = ("TheKey", 42.0).into();
+    /// assert_eq!(KeyValuePair::new("TheKey", 42.0), kv);
+    ///
+    /// let mut map = IndexMap::new();
+    /// map.insert("Hello", 1.0);
+    /// map.insert("World", 2.0);
+    ///
+    /// let list: Vec<_> = map.into_iter().map(KeyValuePair::from).collect();
+    /// assert_eq!(vec![KeyValuePair::new("Hello", 1.0), KeyValuePair::new("World", 2.0)], list);
+    /// ~~~
+    fn from((key, value): (K, V)) -> Self {
+        Self::new(key, value)
+    }
+}
+
+impl<K, V> From<KeyValuePair<K, V>> for (K, V) {
+    /// Convert a `KeyValuePair` into a `(key, value)` tuple.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use indexmap::IndexMap;
+    /// let (key, value) = KeyValuePair::new("TheKey", 42.0).into();
+    /// assert_eq!("TheKey", key);
+    /// assert_eq!(42.0, value);
+    ///
+    /// let list = vec![KeyValuePair::new("Hello", 1.0), KeyValuePair::new("World", 2.0)];
+    /// let map: IndexMap<_, _> = list.into_iter().map(<(_, _)>::from).collect();
+    /// assert_eq!(Some(&2.0), map.get("World"));
+    /// ~~~
+    fn from(kv: KeyValuePair<K, V>) -> Self {
+        (kv.key, kv.value)
+    }
+}
@@ -10,18 +10,42 @@
 //! serialize it into JSON, then dispose of the data variable.  The deserialization story is similar.
 //!
 //! Error values also borrow heavily from the input fields as these errors are expected to be handled
-//! as soon as possible.
+//! as soon as possible.  Use [`OpenProtocolError::into_owned`] to keep an error beyond the lifetime
+//! of the input.
 //!
 //! The result is minimal allocations and copying, but at the cost of stricter lifetime management,
 //! especially when deserializing -- the message struct cannot out-live the original JSON text string as
 //! fields are borrowed extensively from the original JSON string.
 //!
-//! Another implication due to extensive usage of borrowed string slices is that string literals with
-//! escape sequences will cause parsing errors because the actual string cannot be simply borrowed from
-//! the original JSON string.  Luckily this is extremely rare for most fields holding names, ID's etc.
-//! For this reason, only certain user-defined text fields (such as `job_card_id`) may contain
-//! escaped characters (especially the double-quote); those are therefore modeled using `Cow<&str>` instead.
+//! Text fields are modeled using `Cow<str>` and borrow from the original JSON string whenever possible.
+//! String literals with escape sequences cannot be simply borrowed from the original JSON string, so
+//! they are copied instead.  Luckily this is extremely rare for most fields holding names, ID's etc.
 //!
+//! When a `Message` must out-live the original JSON string, use [`Message::parse_from_json_str_owned`]
+//! instead, which copies all text fields (at the cost of more allocations) and returns a `Message<'static>`.
+//!
+//! Message sequence numbers are auto-incremented from a process-global atomic counter.
+//! On targets without 64-bit atomics, or to manage sequence numbers manually, enable the
+//! `manual-sequence` feature -- sequence numbers then default to 0 and must be set explicitly
+//! via [`MessageOptions::set_sequence`].
+//!
+//! To parse hand-edited or imperfectly-captured JSON text with comments or trailing commas,
+//! enable the `lenient-json` feature.
+//!
+//! An unknown controller address ([`Address::Unknown`]) is serialized as `0.0.0.0:0`.  For consumers
+//! that expect JSON `null` instead, enable the `null-unknown-address` feature.  A `null` address is
+//! always deserialized as `Address::Unknown`.
+//!
+//! Timestamps are represented by `chrono::DateTime<FixedOffset>`.  For constrained targets that only
+//! forward messages, disable default features (i.e. the `chrono` feature) to drop the `chrono`
+//! dependency -- timestamps are then kept as RFC3339 text strings (validated by a lightweight regex)
+//! which cannot be used for date/time arithmetic.  See [`Timestamp`].
+//!
+//! [`Address::Unknown`]: enum.Address.html#variant.Unknown
+//! [`Message::parse_from_json_str_owned`]: enum.Message.html#method.parse_from_json_str_owned
+//! [`OpenProtocolError::into_owned`]: enum.OpenProtocolError.html#method.into_owned
+//! [`MessageOptions::set_sequence`]: struct.MessageOptions.html#method.set_sequence
+//! [`Timestamp`]: type.Timestamp.html
 //! [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/cs/doc/messages_reference.md
 //!
 
@@ -31,6 +55,7 @@
 // Modules
 mod address;
 mod controller;
+mod deduper;
 mod error;
 mod filters;
 mod geo_location;
@@ -38,6 +63,10 @@ mod job_card;
 mod key_value_pair;
 mod messages;
 mod operator;
+mod optional_field;
+mod pending;
+pub mod prelude;
+mod session;
 mod state_values;
 mod text;
 mod types;
@@ -49,19 +78,39 @@ pub type Result<'a, T> = std::result::Result<T, Error<'a>>;
 /// Result error type.
 pub type Error<'a> = OpenProtocolError<'a>;
 
+/// Timestamp type, with a time zone offset.
+#[cfg(feature = "chrono")]
+pub type Timestamp<'a> = chrono::DateTime<chrono::FixedOffset>;
+
+/// Timestamp type, as an RFC3339 text string with a time zone offset
+/// (e.g. `2019-02-26T02:03:04+08:00`).
+///
+/// The `chrono` feature is disabled, so timestamps are kept as text strings which
+/// cannot be used for date/time arithmetic.
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp<'a> = text::ConstrainedText<std::borrow::Cow<'a, str>, text::Rfc3339>;
+
 /// 32-bit real floating-point number.
 pub use noisy_float::types::R32;
 
 // Re-exports
-pub use address::Address;
-pub use controller::Controller;
+pub use address::{Address, Transport};
+pub use controller::{Controller, ControllerBuilder};
+pub use deduper::MessageDeduper;
 pub use error::OpenProtocolError;
-pub use filters::Filters;
+pub use filters::{Filters, FiltersIter};
 pub use geo_location::GeoLocation;
 pub use job_card::JobCard;
 pub use key_value_pair::KeyValuePair;
 pub use messages::*;
 pub use operator::Operator;
+pub use optional_field::OptionalField;
+pub use pending::PendingRequests;
+pub use session::{Session, SessionState};
 pub use state_values::StateValues;
-pub use text::{TextID, TextName};
-pub use types::{ActionID, JobMode, Language, OpMode, ID};
+pub use text::{StrictID, TextID, TextName};
+pub use types::{
+    validate_offline_consistency, ActionCategory, ActionID, JobMode, Language, NumericValue,
+    OpMode, ID,
+};
+pub use utils::{build_data_map, r32_from_f64_checked};
@@ -1,20 +1,205 @@
 use super::filters::Filters;
 use super::utils::*;
 use super::{
-    ActionID, Controller, Error, JobCard, JobMode, KeyValuePair, Language, OpMode, Result,
-    StateValues, TextID, TextName, ID, R32,
+    validate_offline_consistency, ActionID, Controller, Error, JobCard, JobMode, KeyValuePair,
+    Language, NumericValue, OpMode, Result, StateValues, TextID, TextName, Timestamp, ID, R32,
 };
-use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use derive_more::*;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::convert::TryInto;
+use std::io::Write;
+#[cfg(not(feature = "manual-sequence"))]
 use std::sync::atomic::{AtomicU64, Ordering};
 use Message::*;
 
 // Auto-incrementing global counter for message sequence numbers.
+#[cfg(not(feature = "manual-sequence"))]
 static SEQ: AtomicU64 = AtomicU64::new(1);
 
+// Get the next message sequence number.
+#[cfg(not(feature = "manual-sequence"))]
+fn next_sequence() -> u64 {
+    SEQ.fetch_add(1, Ordering::SeqCst)
+}
+
+// With the `manual-sequence` feature, the sequence number must be set explicitly.
+#[cfg(feature = "manual-sequence")]
+fn next_sequence() -> u64 {
+    0
+}
+
+/// The type of an Open Protocol [`Message`], without any data.
+///
+/// This is a lightweight `Copy` type useful for routing and metrics (e.g. as the key of
+/// a `HashMap` counting messages of each type).
+///
+/// [`Message`]: enum.Message.html
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # use std::collections::HashMap;
+/// let messages = vec![Message::new_alive(), Message::new_join("hello", Filters::All), Message::new_alive()];
+///
+/// let mut counts: HashMap<MessageKind, u64> = HashMap::new();
+/// for msg in &messages {
+///     *counts.entry(msg.kind()).or_default() += 1;
+/// }
+///
+/// assert_eq!(2, counts[&MessageKind::Alive]);
+/// assert_eq!(1, counts[&MessageKind::Join]);
+/// assert!(!counts.contains_key(&MessageKind::CycleData));
+/// ~~~
+#[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum MessageKind {
+    /// [`Alive`](enum.Message.html#variant.Alive)
+    Alive,
+    /// [`ControllerAction`](enum.Message.html#variant.ControllerAction)
+    ControllerAction,
+    /// [`RequestControllersList`](enum.Message.html#variant.RequestControllersList)
+    RequestControllersList,
+    /// [`ControllersList`](enum.Message.html#variant.ControllersList)
+    ControllersList,
+    /// [`ControllerStatus`](enum.Message.html#variant.ControllerStatus)
+    ControllerStatus,
+    /// [`CycleData`](enum.Message.html#variant.CycleData)
+    CycleData,
+    /// [`RequestJobCardsList`](enum.Message.html#variant.RequestJobCardsList)
+    RequestJobCardsList,
+    /// [`JobCardsList`](enum.Message.html#variant.JobCardsList)
+    JobCardsList,
+    /// [`Join`](enum.Message.html#variant.Join)
+    Join,
+    /// [`JoinResponse`](enum.Message.html#variant.JoinResponse)
+    JoinResponse,
+    /// [`RequestMoldData`](enum.Message.html#variant.RequestMoldData)
+    RequestMoldData,
+    /// [`MoldData`](enum.Message.html#variant.MoldData)
+    MoldData,
+    /// [`ReadMoldData`](enum.Message.html#variant.ReadMoldData)
+    ReadMoldData,
+    /// [`MoldDataValue`](enum.Message.html#variant.MoldDataValue)
+    MoldDataValue,
+    /// [`LoginOperator`](enum.Message.html#variant.LoginOperator)
+    LoginOperator,
+    /// [`OperatorInfo`](enum.Message.html#variant.OperatorInfo)
+    OperatorInfo,
+}
+
+impl MessageKind {
+    // All message types.
+    const ALL: &'static [MessageKind] = &[
+        MessageKind::Alive,
+        MessageKind::ControllerAction,
+        MessageKind::RequestControllersList,
+        MessageKind::ControllersList,
+        MessageKind::ControllerStatus,
+        MessageKind::CycleData,
+        MessageKind::RequestJobCardsList,
+        MessageKind::JobCardsList,
+        MessageKind::Join,
+        MessageKind::JoinResponse,
+        MessageKind::RequestMoldData,
+        MessageKind::MoldData,
+        MessageKind::ReadMoldData,
+        MessageKind::MoldDataValue,
+        MessageKind::LoginOperator,
+        MessageKind::OperatorInfo,
+    ];
+
+    /// Get the name of the message type, as used in the `$type` field of the JSON representation.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!("ControllerStatus", MessageKind::ControllerStatus.as_str());
+    /// assert_eq!("Alive", Message::new_alive().kind().as_str());
+    /// ~~~
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageKind::Alive => "Alive",
+            MessageKind::ControllerAction => "ControllerAction",
+            MessageKind::RequestControllersList => "RequestControllersList",
+            MessageKind::ControllersList => "ControllersList",
+            MessageKind::ControllerStatus => "ControllerStatus",
+            MessageKind::CycleData => "CycleData",
+            MessageKind::RequestJobCardsList => "RequestJobCardsList",
+            MessageKind::JobCardsList => "JobCardsList",
+            MessageKind::Join => "Join",
+            MessageKind::JoinResponse => "JoinResponse",
+            MessageKind::RequestMoldData => "RequestMoldData",
+            MessageKind::MoldData => "MoldData",
+            MessageKind::ReadMoldData => "ReadMoldData",
+            MessageKind::MoldDataValue => "MoldDataValue",
+            MessageKind::LoginOperator => "LoginOperator",
+            MessageKind::OperatorInfo => "OperatorInfo",
+        }
+    }
+
+    // Names of the JSON fields of this message type, excluding the `$type` field and
+    // the fields in `MessageOptions`.
+    fn field_names(self) -> &'static [&'static str] {
+        match self {
+            MessageKind::Alive => &[],
+            MessageKind::ControllerAction => &["controllerId", "actionId", "timestamp"],
+            MessageKind::RequestControllersList
+            | MessageKind::RequestJobCardsList
+            | MessageKind::RequestMoldData => &["controllerId"],
+            MessageKind::ControllersList => &["data"],
+            MessageKind::ControllerStatus => &[
+                "controllerId",
+                "displayName",
+                "isDisconnected",
+                "opMode",
+                "jobMode",
+                "alarm",
+                "audit",
+                "variable",
+                "operatorId",
+                "operatorName",
+                "jobCardId",
+                "moldId",
+                "state",
+                "controller",
+            ],
+            MessageKind::CycleData | MessageKind::MoldData => &[
+                "controllerId",
+                "data",
+                "timestamp",
+                "opMode",
+                "jobMode",
+                "operatorId",
+                "jobCardId",
+                "moldId",
+            ],
+            MessageKind::JobCardsList => &["controllerId", "data"],
+            MessageKind::Join => &["orgId", "version", "password", "language", "filter"],
+            MessageKind::JoinResponse => &["result", "level", "message"],
+            MessageKind::ReadMoldData => &["controllerId", "field"],
+            MessageKind::MoldDataValue => &["controllerId", "field", "value"],
+            MessageKind::LoginOperator => &["controllerId", "password"],
+            MessageKind::OperatorInfo => {
+                &["controllerId", "operatorId", "name", "password", "level"]
+            }
+        }
+    }
+
+    // Is a JSON field name known for this message type?
+    fn is_known_field(self, name: &str) -> bool {
+        match name {
+            "$type" | "id" | "sequence" | "priority" => true,
+            name => self.field_names().contains(&name),
+        }
+    }
+}
+
 /// Common options of an Open Protocol message.
 ///
 #[derive(Debug, Hash, Clone, Serialize, Deserialize)]
@@ -31,6 +216,9 @@ pub struct MessageOptions<'a> {
     /// Ever-increasing message sequence number.
     ///
     /// This number is usually auto-incremented with each message created, starting from 1.
+    ///
+    /// With the `manual-sequence` feature, this number defaults to 0 and must be set explicitly
+    /// via `set_sequence`.
     sequence: u64,
     //
     /// Priority of the message, smaller number is higher priority.  Default = 0.
@@ -63,7 +251,8 @@ impl<'a> MessageOptions<'a> {
     ///
     /// # Examples
     ///
-    /// ~~~
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
     /// # use ichen_openprotocol::*;
     /// let opt1 = MessageOptions::new();
     /// assert_eq!(1, opt1.sequence());
@@ -123,6 +312,23 @@ impl<'a> MessageOptions<'a> {
         Ok(())
     }
 
+    /// Set the message sequence number.
+    ///
+    /// This is usually not needed because the sequence number auto-increments, unless the
+    /// `manual-sequence` feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut opt = MessageOptions::new();
+    /// opt.set_sequence(999);
+    /// assert_eq!(999, opt.sequence());
+    /// ~~~
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
     /// Set the message ID to `None`.
     ///
     /// # Examples
@@ -147,7 +353,8 @@ impl<'a> MessageOptions<'a> {
     ///
     /// # Examples
     ///
-    /// ~~~
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
     /// # use ichen_openprotocol::*;
     /// let opt1 = MessageOptions::new();
     /// assert_eq!(1, opt1.sequence());
@@ -166,7 +373,8 @@ impl<'a> MessageOptions<'a> {
     ///
     /// # Examples
     ///
-    /// ~~~
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
     /// # use ichen_openprotocol::*;
     /// let opt1 = MessageOptions::new_with_priority(100);
     /// assert_eq!(1, opt1.sequence());
@@ -179,16 +387,46 @@ impl<'a> MessageOptions<'a> {
     pub fn new_with_priority(priority: i32) -> Self {
         Self { priority, ..Self::new() }
     }
+
+    /// Compare two `MessageOptions`, ignoring the `sequence` field.
+    ///
+    /// Only the `id` and `priority` fields are compared.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut opt1 = MessageOptions::new_with_priority(100);
+    /// let mut opt2 = MessageOptions::new_with_priority(100);
+    /// opt2.set_sequence(opt1.sequence() + 1);
+    /// assert_ne!(opt1.sequence(), opt2.sequence());
+    /// assert!(opt1.eq_ignoring_sequence(&opt2));
+    ///
+    /// opt1.set_id("hello")?;
+    /// assert!(!opt1.eq_ignoring_sequence(&opt2));
+    /// opt2.set_id("hello")?;
+    /// assert!(opt1.eq_ignoring_sequence(&opt2));
+    ///
+    /// assert!(!opt1.eq_ignoring_sequence(&MessageOptions::new()));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn eq_ignoring_sequence(&self, other: &MessageOptions<'_>) -> bool {
+        self.id() == other.id() && self.priority == other.priority
+    }
 }
 
 impl Default for MessageOptions<'_> {
     /// Default value for `MessageOptions`.
     ///
-    /// The `sequence` field is auto-incrementing.
+    /// The `sequence` field is auto-incrementing, unless the `manual-sequence` feature is enabled,
+    /// in which case it is always 0.
     ///
     /// # Examples
     ///
-    /// ~~~
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
     /// # use ichen_openprotocol::*;
     /// let opt1: MessageOptions = Default::default();
     /// assert_eq!(1, opt1.sequence());
@@ -199,7 +437,109 @@ impl Default for MessageOptions<'_> {
     /// assert_eq!(0, opt2.priority());
     /// ~~~
     fn default() -> Self {
-        Self { id: None, sequence: SEQ.fetch_add(1, Ordering::SeqCst), priority: 0 }
+        Self { id: None, sequence: next_sequence(), priority: 0 }
+    }
+}
+
+/// Options for parsing an Open Protocol message from JSON.
+///
+/// See [`Message::parse_from_json_str_with_options`].
+///
+/// [`Message::parse_from_json_str_with_options`]: enum.Message.html#method.parse_from_json_str_with_options
+///
+/// More options may be added in the future, so create a `ParseOptions` from its default
+/// value and change the options via the builder-style setters.
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// let opt = ParseOptions::new().max_depth(8).max_map_entries(1000).deny_unknown_fields(true);
+/// assert_eq!(8, opt.max_depth);
+/// assert_eq!(1000, opt.max_map_entries);
+/// assert!(opt.deny_unknown_fields);
+/// ~~~
+///
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Maximum nesting depth of JSON objects and arrays.  Default = [`DEFAULT_MAX_DEPTH`].
+    ///
+    /// A valid Open Protocol message never nests deeper than a few levels, so deeply-nested
+    /// JSON text is rejected before it is deserialized.  Regardless of this setting,
+    /// `serde_json`'s own recursion limit (128) always stays in effect.
+    ///
+    /// [`DEFAULT_MAX_DEPTH`]: struct.ParseOptions.html#associatedconstant.DEFAULT_MAX_DEPTH
+    pub max_depth: usize,
+    //
+    /// Maximum number of entries in a data dictionary (e.g. the `data` field of a `CycleData`
+    /// message).  Default = [`DEFAULT_MAX_MAP_ENTRIES`].
+    ///
+    /// A message with more entries is rejected with an error.  However, this limit is checked
+    /// _after_ the entire JSON text is buffered and tokenized, so it does **not** limit the
+    /// memory used during parsing.  Callers (e.g. servers receiving messages from untrusted
+    /// peers) **must** cap the length of the JSON text before parsing it.
+    ///
+    /// [`DEFAULT_MAX_MAP_ENTRIES`]: struct.ParseOptions.html#associatedconstant.DEFAULT_MAX_MAP_ENTRIES
+    pub max_map_entries: usize,
+    //
+    /// Reject unknown top-level fields (similar to `#[serde(deny_unknown_fields)]`) instead of
+    /// silently ignoring them.  Default = `false`.
+    ///
+    /// This is useful for catching protocol drift.  It is slower because the JSON text
+    /// is processed more than once.
+    pub deny_unknown_fields: bool,
+}
+
+impl ParseOptions {
+    /// Default maximum nesting depth of JSON objects and arrays: 16.
+    pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+    /// Default maximum number of entries in a data dictionary: 100,000.
+    pub const DEFAULT_MAX_MAP_ENTRIES: usize = 100_000;
+
+    /// Create a new `ParseOptions` with default values.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the maximum nesting depth of JSON objects and arrays.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the maximum number of entries in a data dictionary.
+    pub fn max_map_entries(mut self, max_map_entries: usize) -> Self {
+        self.max_map_entries = max_map_entries;
+        self
+    }
+
+    /// Set whether to reject unknown top-level fields.
+    pub fn deny_unknown_fields(mut self, deny: bool) -> Self {
+        self.deny_unknown_fields = deny;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    /// Default value for `ParseOptions`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let opt: ParseOptions = Default::default();
+    /// assert_eq!(ParseOptions::DEFAULT_MAX_DEPTH, opt.max_depth);
+    /// assert_eq!(ParseOptions::DEFAULT_MAX_MAP_ENTRIES, opt.max_map_entries);
+    /// assert!(!opt.deny_unknown_fields);
+    /// ~~~
+    fn default() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_map_entries: Self::DEFAULT_MAX_MAP_ENTRIES,
+            deny_unknown_fields: false,
+        }
     }
 }
 
@@ -224,6 +564,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     ControllerAction {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         /// Unique action code.
         ///
@@ -233,7 +574,7 @@ pub enum Message<'a> {
         action_id: ActionID,
         //
         /// Time-stamp of the event.
-        timestamp: DateTime<FixedOffset>,
+        timestamp: Timestamp<'a>,
         //
         /// Message configuration options.
         #[serde(flatten)]
@@ -290,6 +631,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     ControllerStatus {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Human-friendly name for display (or `None` if not relevant).
@@ -297,7 +639,11 @@ pub enum Message<'a> {
         display_name: Option<Box<TextName<'a>>>,
         //
         /// If true, the controller has disconnected from the iChen® Server.
+        ///
+        /// The integers `0` and `1` are also accepted as `false` and `true` during deserialization.
+        #[serde(deserialize_with = "deserialize_bool_or_int")]
         #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
         is_disconnected: Option<bool>,
         //
         /// Current operation mode of the controller (or `None` if not relevant).
@@ -319,11 +665,11 @@ pub enum Message<'a> {
         /// Change of a setting (if any) on the controller for audit trail purpose
         /// (or `None` if not relevant).
         #[serde(skip_serializing_if = "Option::is_none")]
-        audit: Option<Box<KeyValuePair<TextID<'a>, R32>>>,
+        audit: Option<Box<KeyValuePair<TextID<'a>, NumericValue>>>,
         //
         /// Change of a variable (if any) on the controller (or `None` if not relevant).
         #[serde(skip_serializing_if = "Option::is_none")]
-        variable: Option<Box<KeyValuePair<TextID<'a>, R32>>>,
+        variable: Option<Box<KeyValuePair<TextID<'a>, NumericValue>>>,
         //
         /// Unique ID of the current logged-on user, `Some(None)` if a user has logged out
         /// (or `None` if not relevant).
@@ -381,6 +727,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     CycleData {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// A data dictionary containing a set of cycle data.
@@ -388,10 +735,11 @@ pub enum Message<'a> {
         /// See [this document] for examples.
         ///
         /// [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/doc/cycledata.md
+        #[serde(deserialize_with = "deserialize_bounded_indexmap")]
         data: IndexMap<TextID<'a>, R32>,
         //
         /// Time-stamp of the event.
-        timestamp: DateTime<FixedOffset>,
+        timestamp: Timestamp<'a>,
         //
         /// Snapshot of the current known states of the controller.
         #[serde(flatten)]
@@ -413,6 +761,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     RequestJobCardsList {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Message configuration options.
@@ -426,6 +775,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     JobCardsList {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// A data dictionary containing a set of `JobCard` data structures.
@@ -455,7 +805,8 @@ pub enum Message<'a> {
         version: TextID<'a>,
         //
         /// Password to log onto the server.
-        password: &'a str,
+        #[serde(borrow)]
+        password: Cow<'a, str>,
         //
         /// Language encoding.
         language: Language,
@@ -502,6 +853,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     RequestMoldData {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Message configuration options.
@@ -517,13 +869,15 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     MoldData {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// A data dictionary containing a set of mold settings.
+        #[serde(deserialize_with = "deserialize_bounded_indexmap")]
         data: IndexMap<TextID<'a>, R32>,
         //
         /// Time-stamp of the event.
-        timestamp: DateTime<FixedOffset>,
+        timestamp: Timestamp<'a>,
         //
         /// Snapshot of the current known states of the controller.
         #[serde(flatten)]
@@ -551,9 +905,16 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     ReadMoldData {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Name of the mold setting to read, `None` for all.
+        ///
+        /// A mold setting name is normally a single token (i.e. no white-spaces) not longer than
+        /// [`MAX_SETTING_NAME_LEN`] characters.  This is checked by [`validate_strict`].
+        ///
+        /// [`MAX_SETTING_NAME_LEN`]: enum.Message.html#associatedconstant.MAX_SETTING_NAME_LEN
+        /// [`validate_strict`]: enum.Message.html#method.validate_strict
         field: Option<TextID<'a>>,
         //
         /// Message configuration options.
@@ -568,9 +929,16 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     MoldDataValue {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Name of the mold setting to read.
+        ///
+        /// A mold setting name is normally a single token (i.e. no white-spaces) not longer than
+        /// [`MAX_SETTING_NAME_LEN`] characters.  This is checked by [`validate_strict`].
+        ///
+        /// [`MAX_SETTING_NAME_LEN`]: enum.Message.html#associatedconstant.MAX_SETTING_NAME_LEN
+        /// [`validate_strict`]: enum.Message.html#method.validate_strict
         field: TextID<'a>,
         //
         /// Current cached value of the mold setting.
@@ -592,10 +960,12 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     LoginOperator {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// User password.
-        password: &'a str,
+        #[serde(borrow)]
+        password: Cow<'a, str>,
         //
         /// Message configuration options.
         #[serde(flatten)]
@@ -609,6 +979,7 @@ pub enum Message<'a> {
     #[serde(rename_all = "camelCase")]
     OperatorInfo {
         /// Unique ID of the controller.
+        #[serde(deserialize_with = "deserialize_id_or_string")]
         controller_id: ID,
         //
         /// Unique ID of the authenticated user.
@@ -644,8 +1015,25 @@ impl<'a> Message<'a> {
     /// Maximum operator level: 10.
     pub const MAX_OPERATOR_LEVEL: u8 = 10;
 
+    /// Maximum length of a mold setting name: 64.
+    pub const MAX_SETTING_NAME_LEN: usize = 64;
+
+    /// Placeholder replacing passwords in a redacted message: `********`.
+    pub const REDACTED_PASSWORD: &'static str = "********";
+
     /// Parse a JSON string into a `Message`.
     ///
+    /// A leading UTF-8 byte-order mark (BOM), such as those in files saved by some
+    /// Windows tools, is ignored.
+    ///
+    /// With the `lenient-json` feature, JSON text with comments (`// ...` and `/* ... */`)
+    /// or trailing commas (e.g. `[1, 2,]`) is also accepted.  Such text is cleaned up and
+    /// then parsed via [`parse_from_json_str_owned`], so the resulting `Message` does not
+    /// borrow from `json`.  If the cleaned-up text still cannot be parsed, the original
+    /// parsing error is returned.
+    ///
+    /// [`parse_from_json_str_owned`]: enum.Message.html#method.parse_from_json_str_owned
+    ///
     /// # Errors
     ///
     /// Return `Err(`[`OpenProtocolError`]`)` if there is an error during parsing.
@@ -653,211 +1041,2055 @@ impl<'a> Message<'a> {
     /// [`OpenProtocolError`]: enum.OpenProtocolError.html
     ///
     pub fn parse_from_json_str(json: &'a str) -> Result<'a, Self> {
-        let m = serde_json::from_str::<Message>(json).map_err(Error::JsonError)?;
+        let json = strip_bom(json);
+
+        let m = match serde_json::from_str::<Message>(json) {
+            Ok(m) => m,
+            #[cfg(feature = "lenient-json")]
+            Err(err) if err.is_syntax() => {
+                return match strip_lenient_json(json)
+                    .map(|text| Self::parse_from_json_str_owned(&text))
+                {
+                    // Report the error in the original text if the cleaned text cannot be parsed
+                    Some(Err(Error::JsonError(_))) | None => Err(Error::JsonError(err)),
+                    Some(result) => result,
+                };
+            }
+            Err(err) => return Err(Error::JsonError(err)),
+        };
+
         m.validate()?;
         Ok(m)
     }
 
-    /// Validate all the fields in the `Message`, then serialize it into a JSON string.
+    /// Parse a JSON string into a `Message`, with options.
+    ///
+    /// This is the same as [`parse_from_json_str`], except that the JSON text is first checked
+    /// against the limits in `options` (e.g. the maximum nesting depth and the maximum number
+    /// of entries in a data dictionary), and unknown top-level fields are rejected if
+    /// `options.deny_unknown_fields` is set.
+    ///
+    /// These limits guard against malformed or malicious messages, but the JSON text must
+    /// already be entirely in memory.  Callers must cap the length of the JSON text before
+    /// calling this method.
+    ///
+    /// [`parse_from_json_str`]: enum.Message.html#method.parse_from_json_str
     ///
     /// # Errors
     ///
-    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error.
+    /// Return `Err(`[`OpenProtocolError`]`)` if the JSON text exceeds the limits in `options`,
+    /// or if there is an error during parsing.
     ///
     /// [`OpenProtocolError`]: enum.OpenProtocolError.html
     ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let json = r#"{"$type":"Alive","sequence":1,"extra":[[[[{"a":[1]}]]]]}"#;
+    /// let options = ParseOptions::new().max_depth(4);
+    ///
+    /// match Message::parse_from_json_str_with_options(json, &options) {
+    ///     Err(OpenProtocolError::JsonError(err)) => {
+    ///         assert_eq!("JSON text nests deeper than the maximum depth of 4", err.to_string());
+    ///     }
+    ///     _ => panic!(),
+    /// }
+    ///
+    /// let json = r#"{"$type":"Alive","sequence":1,"extra":42}"#;
+    /// let options = ParseOptions::new().deny_unknown_fields(true);
+    ///
+    /// match Message::parse_from_json_str_with_options(json, &options) {
+    ///     Err(OpenProtocolError::JsonError(err)) => {
+    ///         assert_eq!("unknown field `extra`", err.to_string());
+    ///     }
+    ///     _ => panic!(),
+    /// }
+    /// ~~~
+    ///
     /// # Examples
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
     /// # fn main() -> std::result::Result<(), String> {
-    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
-    /// assert_eq!(
-    ///     r#"{"$type":"Join","orgId":"MyCompany","version":"4.0","password":"MyPassword","language":"EN","filter":"Status, Cycle","sequence":1}"#,
-    ///     msg.to_json_str()?
-    /// );
+    /// let json = r#"{"$type":"Alive","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str_with_options(json, &Default::default())
+    ///                     .map_err(|err| err.to_string())?;
+    /// assert_eq!(1, msg.sequence());
     /// # Ok(())
     /// # }
     /// ~~~
-    pub fn to_json_str(&self) -> Result<'_, String> {
-        self.validate()?;
-        serde_json::to_string(self).map_err(Error::JsonError)
+    pub fn parse_from_json_str_with_options(
+        json: &'a str,
+        options: &ParseOptions,
+    ) -> Result<'a, Self> {
+        let json = strip_bom(json);
+
+        // Run all checks on the text that is actually parsed
+        #[cfg(feature = "lenient-json")]
+        let text = strip_lenient_json(json).map_or(Cow::Borrowed(json), Cow::Owned);
+        #[cfg(not(feature = "lenient-json"))]
+        let text = Cow::Borrowed(json);
+
+        if json_depth_exceeds(&text, options.max_depth) {
+            return Err(Error::JsonError(serde::de::Error::custom(format!(
+                "JSON text nests deeper than the maximum depth of {}",
+                options.max_depth
+            ))));
+        }
+
+        let m = with_max_map_entries(options.max_map_entries, || Self::parse_from_json_str(json))?;
+
+        if options.deny_unknown_fields {
+            check_unknown_fields(m.kind(), &text).map_err(Error::JsonError)?;
+        }
+
+        Ok(m)
     }
 
-    /// Create an `ALIVE` message.
+    /// Parse a JSON string into a `Message` that owns all its data.
+    ///
+    /// Unlike [`parse_from_json_str`], the resulting `Message` does not borrow from the
+    /// original JSON string, and escape sequences are allowed in all text fields.
+    /// This is slower because every text string is copied.
+    ///
+    /// [`parse_from_json_str`]: enum.Message.html#method.parse_from_json_str
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error during parsing.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
     ///
     /// # Examples
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
-    /// let msg = Message::new_alive();
-    /// if let Message::Alive { options } = msg {
-    ///     assert_eq!(1, options.sequence());
-    ///     assert_eq!(0, options.priority());
-    ///     assert_eq!(None, options.id());
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = {
+    ///     let json = String::from(r#"{"$type":"Join","orgId":"My\u0043ompany","version":"4.0","password":"\"hello\"","language":"EN","filter":"All","sequence":1}"#);
+    ///     Message::parse_from_json_str_owned(&json).map_err(|err| err.to_string())?
+    /// };
+    ///
+    /// if let Message::Join { org_id, password, .. } = msg {
+    ///     assert_eq!(Some("MyCompany"), org_id.as_ref().map(|x| x.get()));
+    ///     assert_eq!(r#""hello""#, password);
     /// } else {
     ///     panic!();
     /// }
+    /// # Ok(())
+    /// # }
     /// ~~~
-    pub fn new_alive() -> Self {
-        Alive { options: Default::default() }
+    pub fn parse_from_json_str_owned(json: &str) -> Result<'static, Message<'static>> {
+        let json = strip_bom(json);
+
+        // Reading from a stream never borrows, so all text strings are copied
+        let mut deserializer = serde_json::Deserializer::from_reader(json.as_bytes());
+        let m = Message::deserialize(&mut deserializer).map_err(Error::JsonError)?;
+        deserializer.end().map_err(Error::JsonError)?;
+        m.validate()?;
+        Ok(m)
     }
 
-    /// Create a `JOIN` message with default language and protocol version.
+    /// Parse the first JSON object in a string into a `Message`, returning it together with
+    /// the remaining unparsed input.
     ///
-    /// The default language is [`DEFAULT_LANGUAGE`] (usually `EN`).
+    /// This is useful for parsing a stream of concatenated JSON objects (not necessarily
+    /// separated by new-lines) from a buffer.  The remaining input starts right after the
+    /// end of the first JSON object.
     ///
-    /// The default protocol version is given in [`PROTOCOL_VERSION`].
+    /// # Errors
     ///
-    /// [`DEFAULT_LANGUAGE`]: enum.Message.html#associatedconstant.DEFAULT_LANGUAGE
-    /// [`PROTOCOL_VERSION`]: enum.Message.html#associatedconstant.PROTOCOL_VERSION
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error during parsing, or if the
+    /// input contains no JSON object.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
     ///
     /// # Examples
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
-    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
-    /// if let Message::Join { org_id, version, password, language, filter, options } = msg {
-    ///     assert_eq!(None, org_id);
-    ///     assert_eq!(Message::PROTOCOL_VERSION, &version);
-    ///     assert_eq!("MyPassword", password);
-    ///     assert_eq!(Message::DEFAULT_LANGUAGE, language);
-    ///     assert_eq!(Filters::Status + Filters::Cycle, filter);
-    ///     assert_eq!(1, options.sequence());
-    ///     assert_eq!(0, options.priority());
-    ///     assert_eq!(None, options.id());
-    /// } else {
-    ///     panic!();
-    /// }
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"Alive","sequence":1}{"$type":"Alive","sequence":2}"#;
+    ///
+    /// let (msg, rest) = Message::parse_first_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert_eq!(1, msg.sequence());
+    /// assert_eq!(r#"{"$type":"Alive","sequence":2}"#, rest);
+    ///
+    /// let (msg, rest) = Message::parse_first_from_json_str(rest).map_err(|err| err.to_string())?;
+    /// assert_eq!(2, msg.sequence());
+    /// assert_eq!("", rest);
+    ///
+    /// assert!(Message::parse_first_from_json_str(rest).is_err());
+    /// # Ok(())
+    /// # }
     /// ~~~
-    pub fn new_join(password: &'a str, filter: Filters) -> Self {
-        Join {
-            org_id: None,
-            version: Self::PROTOCOL_VERSION.try_into().unwrap(),
-            password,
-            language: Self::DEFAULT_LANGUAGE,
-            filter,
-            options: Default::default(),
-        }
+    pub fn parse_first_from_json_str(json: &'a str) -> Result<'a, (Self, &'a str)> {
+        let json = strip_bom(json);
+        let mut stream = serde_json::Deserializer::from_str(json).into_iter::<Message>();
+
+        let m = match stream.next() {
+            Some(m) => m.map_err(Error::JsonError)?,
+            None => {
+                return Err(Error::JsonError(serde::de::Error::custom("EOF while parsing a value")))
+            }
+        };
+
+        m.validate()?;
+        Ok((m, &json[stream.byte_offset()..]))
     }
 
-    /// Create a `JOIN` message with non-default organization.
+    /// Parse a JSON string into a `Message` that owns all its data, matching the `$type`
+    /// discriminator case-insensitively (e.g. `"controllerstatus"` for `"ControllerStatus"`).
     ///
-    /// # Errors
+    /// This is useful for data from inconsistent sources (e.g. hand-written test fixtures).
+    /// It is slower than [`parse_from_json_str_owned`] because the JSON string is
+    /// processed twice.  All other fields are parsed as usual.
     ///
-    /// Returns `Err(String)` if the organization ID is empty or all-whitespace or contains
-    /// any non-ASCII characters.
+    /// [`parse_from_json_str_owned`]: enum.Message.html#method.parse_from_json_str_owned
     ///
-    /// ## Error Examples
+    /// # Errors
     ///
-    /// ~~~
-    /// # use ichen_openprotocol::*;
-    /// match Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "") {
-    ///     Err(e) => assert_eq!("invalid value: a non-empty, non-whitespace, all-ASCII string required", e),
-    ///     _ => ()
-    /// }
-    /// ~~~
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error during parsing.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
     ///
     /// # Examples
     ///
     /// ~~~
     /// # use ichen_openprotocol::*;
     /// # fn main() -> std::result::Result<(), String> {
-    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+    /// let json = r#"{"$type":"joinRESPONSE","result":100,"level":5,"sequence":1}"#;
     ///
-    /// if let Message::Join { org_id, version, password, language, filter, options } = msg {
-    ///     assert_eq!(Some("MyCompany"), org_id.as_ref().map(|x| x.get()));
-    ///     assert_eq!(Message::PROTOCOL_VERSION, version.get());
-    ///     assert_eq!("MyPassword", password);
-    ///     assert_eq!(Message::DEFAULT_LANGUAGE, language);
-    ///     assert_eq!(Filters::Status + Filters::Cycle, filter);
-    ///     assert_eq!(1, options.sequence());
-    ///     assert_eq!(0, options.priority());
-    ///     assert_eq!(None, options.id());
+    /// // The default is strict
+    /// assert!(Message::parse_from_json_str(json).is_err());
+    ///
+    /// let msg = Message::parse_from_json_str_case_insensitive(json).map_err(|err| err.to_string())?;
+    ///
+    /// if let Message::JoinResponse { result, level, .. } = msg {
+    ///     assert_eq!(100, result);
+    ///     assert_eq!(Some(5), level);
     /// } else {
     ///     panic!();
     /// }
     /// # Ok(())
     /// # }
     /// ~~~
-    pub fn try_new_join_with_org(
-        password: &'a str,
-        filter: Filters,
-        org: &'a str,
-    ) -> std::result::Result<Self, String> {
-        let mut msg = Self::new_join(password, filter);
+    pub fn parse_from_json_str_case_insensitive(json: &str) -> Result<'static, Message<'static>> {
+        use serde_json::value::RawValue;
 
-        if let Join { ref mut org_id, .. } = msg {
-            *org_id = Some(org.try_into()?);
-        }
+        let json = strip_bom(json);
 
-        Ok(msg)
-    }
+        // Keep all fields (in order) as raw JSON, only replacing the `$type` discriminator
+        let mut fields = raw_fields(json).map_err(Error::JsonError)?;
+
+        let tag = match fields.get("$type") {
+            Some(value) => serde_json::from_str::<Cow<str>>(value.get()).ok(),
+            None => None,
+        };
+
+        let canonical = tag.and_then(|tag| {
+            MessageKind::ALL
+                .iter()
+                .map(MessageKind::as_str)
+                .find(|name| name.eq_ignore_ascii_case(&tag) && *name != tag)
+        });
+
+        match canonical {
+            Some(name) => {
+                let value = serde_json::to_string(name).map_err(Error::JsonError)?;
+                let value = RawValue::from_string(value).map_err(Error::JsonError)?;
+                fields.insert("$type".into(), &value);
+
+                let json = serde_json::to_string(&fields).map_err(Error::JsonError)?;
+                Self::parse_from_json_str_owned(&json)
+            }
+            None => Self::parse_from_json_str_owned(json),
+        }
+    }
+
+    /// Parse a JSON string into a `Message` that owns all its data, with the `$type`
+    /// discriminator under a different key (e.g. `type` or `messageType`).
+    ///
+    /// This is the reverse of [`to_json_str_with_type_key`].  The JSON string is processed
+    /// twice, then parsed via [`parse_from_json_str_owned`].
+    ///
+    /// [`to_json_str_with_type_key`]: enum.Message.html#method.to_json_str_with_type_key
+    /// [`parse_from_json_str_owned`]: enum.Message.html#method.parse_from_json_str_owned
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error during parsing, or if the
+    /// JSON string also contains a `$type` field.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let json = r#"{"type":"Alive","$type":"Join","sequence":42}"#;
+    ///
+    /// assert_eq!(
+    ///     Some(Error::ConstraintViolated("Type key [type] conflicts with the $type field of the message.".into())),
+    ///     Message::parse_from_json_str_with_type_key(json, "type").err()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"type":"Alive","sequence":42}"#;
+    ///
+    /// let msg = Message::parse_from_json_str_with_type_key(json, "type").map_err(|err| err.to_string())?;
+    /// assert_eq!(MessageKind::Alive, msg.kind());
+    /// assert_eq!(42, msg.sequence());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn parse_from_json_str_with_type_key(
+        json: &str,
+        key: &str,
+    ) -> Result<'static, Message<'static>> {
+        let json = strip_bom(json);
+
+        if key == "$type" {
+            return Self::parse_from_json_str_owned(json);
+        }
+
+        let fields = raw_fields(json).map_err(Error::JsonError)?;
+
+        if fields.contains_key("$type") {
+            return Err(Error::ConstraintViolated(
+                format!("Type key [{}] conflicts with the $type field of the message.", key).into(),
+            ));
+        }
+
+        let fields = rename_raw_field(fields, key, "$type");
+        let json = serde_json::to_string(&fields).map_err(Error::JsonError)?;
+        Self::parse_from_json_str_owned(&json)
+    }
+
+    /// Get a concise, human-readable description of the `Message` for debugging, showing
+    /// only the fields that are present.
+    ///
+    /// Fields are shown with their JSON names and values, in the same order as in JSON.
+    /// Unlike the derived `Debug` output, fields that are `None` are not shown.
+    /// Passwords are replaced by a placeholder (see [`redact`]), so the description is
+    /// safe for logging.  The `Message` is not validated.
+    ///
+    /// [`redact`]: enum.Message.html#method.redact
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
+    /// assert_eq!(
+    ///     format!(r#"Join {{ version: "4.0", password: "********", language: "EN", filter: "Status, Cycle", sequence: {} }}"#, msg.sequence()),
+    ///     msg.describe()
+    /// );
+    /// ~~~
+    pub fn describe(&self) -> String {
+        let msg = self.redact();
+
+        let json = match serde_json::to_string(&msg) {
+            Ok(json) => json,
+            Err(_) => return format!("{:?}", msg),
+        };
+
+        let fields = match raw_fields(&json) {
+            Ok(fields) => fields,
+            Err(_) => return format!("{:?}", msg),
+        };
+
+        let fields: Vec<_> = fields
+            .iter()
+            .filter(|(k, _)| *k != "$type")
+            .map(|(k, v)| format!("{}: {}", k, v.get()))
+            .collect();
+
+        format!("{} {{ {} }}", self.kind(), fields.join(", "))
+    }
+
+    /// Validate all the fields in the `Message`, then serialize it into a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+    /// assert_eq!(
+    ///     r#"{"$type":"Join","orgId":"MyCompany","version":"4.0","password":"MyPassword","language":"EN","filter":"Status, Cycle","sequence":1}"#,
+    ///     msg.to_json_str()?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn to_json_str(&self) -> Result<'_, String> {
+        self.validate()?;
+        serde_json::to_string(self).map_err(Error::JsonError)
+    }
+
+    /// Validate all the fields in the `Message`, then serialize it into a JSON string, with the
+    /// `$type` discriminator renamed to `key` (e.g. `type` or `messageType`).
+    ///
+    /// This is useful for systems that cannot handle the `$type` key.
+    /// Use [`parse_from_json_str_with_type_key`] to parse the output back into a `Message`.
+    ///
+    /// [`parse_from_json_str_with_type_key`]: enum.Message.html#method.parse_from_json_str_with_type_key
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error, or if `key` is the same
+    /// as the name of another field in the message.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
+    /// assert_eq!(
+    ///     Err(Error::ConstraintViolated("Type key [password] conflicts with a field of the message.".into())),
+    ///     msg.to_json_str_with_type_key("password")
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::parse_from_json_str(r#"{"$type":"Alive","sequence":42,"priority":10}"#)?;
+    /// assert_eq!(
+    ///     r#"{"messageType":"Alive","sequence":42,"priority":10}"#,
+    ///     msg.to_json_str_with_type_key("messageType")?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn to_json_str_with_type_key(&self, key: &str) -> Result<'_, String> {
+        let json = self.to_json_str()?;
+
+        if key == "$type" {
+            return Ok(json);
+        }
+
+        let fields = raw_fields(&json).map_err(Error::JsonError)?;
+
+        if fields.contains_key(key) {
+            return Err(Error::ConstraintViolated(
+                format!("Type key [{}] conflicts with a field of the message.", key).into(),
+            ));
+        }
+
+        let fields = rename_raw_field(fields, "$type", key);
+        serde_json::to_string(&fields).map_err(Error::JsonError)
+    }
+
+    /// Validate all the fields in the `Message`, then serialize it as JSON into a writer
+    /// (e.g. a file or a socket buffer) without building an intermediate `String`.
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error, including any I/O error
+    /// while writing.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+    ///
+    /// let mut buf: Vec<u8> = Vec::new();
+    /// msg.write_json(&mut buf)?;
+    ///
+    /// assert_eq!(
+    ///     r#"{"$type":"Join","orgId":"MyCompany","version":"4.0","password":"MyPassword","language":"EN","filter":"Status, Cycle","sequence":1}"#,
+    ///     String::from_utf8(buf).unwrap()
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<'_, ()> {
+        self.validate()?;
+        serde_json::to_writer(writer, self).map_err(Error::JsonError)
+    }
+
+    /// Validate all the fields in the `Message`, then serialize it into a JSON string
+    /// no longer than `max_len` bytes.
+    ///
+    /// This is useful to avoid sending frames larger than the server's WebSocket limit.
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(`[`OpenProtocolError`]`)` if there is an error, or if the serialized
+    /// JSON string is longer than `max_len` bytes.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
+    /// assert_eq!(
+    ///     Err(Error::ConstraintViolated("Serialized message is 110 bytes, exceeding the maximum of 50 bytes.".into())),
+    ///     msg.to_json_str_bounded(50)
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
+    /// assert_eq!(
+    ///     r#"{"$type":"Join","version":"4.0","password":"MyPassword","language":"EN","filter":"Status, Cycle","sequence":1}"#,
+    ///     msg.to_json_str_bounded(1000)?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn to_json_str_bounded(&self, max_len: usize) -> Result<'_, String> {
+        let json = self.to_json_str()?;
+
+        if json.len() > max_len {
+            return Err(Error::ConstraintViolated(
+                format!(
+                    "Serialized message is {} bytes, exceeding the maximum of {} bytes.",
+                    json.len(),
+                    max_len
+                )
+                .into(),
+            ));
+        }
+
+        Ok(json)
+    }
+
+    /// Create an `ALIVE` message.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_alive();
+    /// if let Message::Alive { options } = msg {
+    ///     assert_eq!(1, options.sequence());
+    ///     assert_eq!(0, options.priority());
+    ///     assert_eq!(None, options.id());
+    /// } else {
+    ///     panic!();
+    /// }
+    /// ~~~
+    pub fn new_alive() -> Self {
+        Alive { options: Default::default() }
+    }
+
+    /// Create a `JOIN` message with default language and protocol version.
+    ///
+    /// The default language is [`DEFAULT_LANGUAGE`] (usually `EN`).
+    ///
+    /// The default protocol version is given in [`PROTOCOL_VERSION`].
+    ///
+    /// [`DEFAULT_LANGUAGE`]: enum.Message.html#associatedconstant.DEFAULT_LANGUAGE
+    /// [`PROTOCOL_VERSION`]: enum.Message.html#associatedconstant.PROTOCOL_VERSION
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::Status + Filters::Cycle);
+    /// if let Message::Join { org_id, version, password, language, filter, options } = msg {
+    ///     assert_eq!(None, org_id);
+    ///     assert_eq!(Message::PROTOCOL_VERSION, &version);
+    ///     assert_eq!("MyPassword", password);
+    ///     assert_eq!(Message::DEFAULT_LANGUAGE, language);
+    ///     assert_eq!(Filters::Status + Filters::Cycle, filter);
+    ///     assert_eq!(1, options.sequence());
+    ///     assert_eq!(0, options.priority());
+    ///     assert_eq!(None, options.id());
+    /// } else {
+    ///     panic!();
+    /// }
+    /// ~~~
+    pub fn new_join(password: &'a str, filter: Filters) -> Self {
+        Join {
+            org_id: None,
+            version: Self::PROTOCOL_VERSION.try_into().unwrap(),
+            password: password.into(),
+            language: Self::DEFAULT_LANGUAGE,
+            filter,
+            options: Default::default(),
+        }
+    }
+
+    /// Create a `JOIN` message with non-default organization.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the organization ID is empty or all-whitespace or contains
+    /// any non-ASCII characters.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// match Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "") {
+    ///     Err(e) => assert_eq!("invalid value: a non-empty, non-whitespace, all-ASCII string required", e),
+    ///     _ => ()
+    /// }
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+    ///
+    /// if let Message::Join { org_id, version, password, language, filter, options } = msg {
+    ///     assert_eq!(Some("MyCompany"), org_id.as_ref().map(|x| x.get()));
+    ///     assert_eq!(Message::PROTOCOL_VERSION, version.get());
+    ///     assert_eq!("MyPassword", password);
+    ///     assert_eq!(Message::DEFAULT_LANGUAGE, language);
+    ///     assert_eq!(Filters::Status + Filters::Cycle, filter);
+    ///     assert_eq!(1, options.sequence());
+    ///     assert_eq!(0, options.priority());
+    ///     assert_eq!(None, options.id());
+    /// } else {
+    ///     panic!();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn try_new_join_with_org(
+        password: &'a str,
+        filter: Filters,
+        org: &'a str,
+    ) -> std::result::Result<Self, String> {
+        let mut msg = Self::new_join(password, filter);
+
+        if let Join { ref mut org_id, .. } = msg {
+            *org_id = Some(org.try_into()?);
+        }
+
+        Ok(msg)
+    }
+
+    /// Create an `OperatorInfo` message in reply to a `LoginOperator` message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if `name` or `password` is empty or all white-spaces,
+    /// or if `level` is larger than [`MAX_OPERATOR_LEVEL`].
+    ///
+    /// [`MAX_OPERATOR_LEVEL`]: enum.Message.html#associatedconstant.MAX_OPERATOR_LEVEL
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(
+    ///     "level 11 is too high - must be between 0 and 10",
+    ///     Message::new_operator_info(ID::from_u32(123), None, "John", "MyPassword", 11).unwrap_err()
+    /// );
+    ///
+    /// assert_eq!(
+    ///     "invalid value: a non-empty, non-whitespace string required for operator name",
+    ///     Message::new_operator_info(ID::from_u32(123), None, "   ", "MyPassword", 1).unwrap_err()
+    /// );
+    ///
+    /// assert_eq!(
+    ///     "invalid value: a non-empty, non-whitespace string required for password",
+    ///     Message::new_operator_info(ID::from_u32(123), None, "John", "", 1).unwrap_err()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_operator_info(ID::from_u32(123), Some(ID::from_u32(42)), "John", "MyPassword", 5)?;
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// if let Message::OperatorInfo { controller_id, operator_id, name, password, level, .. } = &msg {
+    ///     assert_eq!(123, *controller_id);
+    ///     assert_eq!(Some(ID::from_u32(42)), *operator_id);
+    ///     assert_eq!("John", name.get());
+    ///     assert_eq!("MyPassword", password.get());
+    ///     assert_eq!(5, *level);
+    /// } else {
+    ///     panic!();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn new_operator_info(
+        controller_id: ID,
+        operator_id: Option<ID>,
+        name: &'a str,
+        password: &'a str,
+        level: u8,
+    ) -> std::result::Result<Self, String> {
+        if level > Self::MAX_OPERATOR_LEVEL {
+            return Err(format!(
+                "level {} is too high - must be between 0 and {}",
+                level,
+                Self::MAX_OPERATOR_LEVEL
+            ));
+        }
+
+        Ok(OperatorInfo {
+            controller_id,
+            operator_id,
+            name: name.try_into().map_err(|e| format!("{} for operator name", e))?,
+            password: password.try_into().map_err(|e| format!("{} for password", e))?,
+            level,
+            options: Default::default(),
+        })
+    }
+
+    /// Create a `REQ_CNTRLER_LIST` message for each of a list of controller ID's.
+    ///
+    /// `RequestControllersList` can only request either one controller or all controllers
+    /// at once.  This creates one message per controller ID, each with its own auto-incremented
+    /// sequence number.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// let ids = [ID::from_u32(1), ID::from_u32(42), ID::from_u32(123)];
+    /// let messages = Message::request_controllers(&ids);
+    /// assert_eq!(3, messages.len());
+    ///
+    /// for (msg, id) in messages.iter().zip(ids.iter()) {
+    ///     if let Message::RequestControllersList { controller_id, .. } = msg {
+    ///         assert_eq!(Some(*id), *controller_id);
+    ///     } else {
+    ///         panic!();
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(1, messages[0].sequence());
+    /// assert_eq!(2, messages[1].sequence());
+    /// assert_eq!(3, messages[2].sequence());
+    /// ~~~
+    pub fn request_controllers(ids: &[ID]) -> Vec<Self> {
+        ids.iter()
+            .map(|&id| RequestControllersList {
+                controller_id: Some(id),
+                options: Default::default(),
+            })
+            .collect()
+    }
+
+    /// Create a `CYCLE_DATA` message with only the op mode and job mode in its state values.
+    ///
+    /// The operator ID, job card ID and mold ID in the state values are all `None`.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use indexmap::indexmap;
+    /// # use chrono::DateTime;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_cycle_data_simple(
+    ///     ID::from_u32(123),
+    ///     indexmap![TextID::new("Z_QDCYCTIM").unwrap() => R32::new(12.5)],
+    ///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+    ///     OpMode::Automatic,
+    ///     JobMode::ID02,
+    /// );
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// if let Message::CycleData { controller_id, state, .. } = &msg {
+    ///     assert_eq!(123, *controller_id);
+    ///     assert_eq!(OpMode::Automatic, state.op_mode());
+    ///     assert_eq!(JobMode::ID02, state.job_mode());
+    ///     assert_eq!(None, state.operator_id());
+    ///     assert_eq!(None, state.job_card_id());
+    ///     assert_eq!(None, state.mold_id());
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert_eq!(Some(12.5), msg.get_value_f32("Z_QDCYCTIM"));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn new_cycle_data_simple(
+        controller_id: ID,
+        data: IndexMap<TextID<'a>, R32>,
+        timestamp: Timestamp<'a>,
+        op: OpMode,
+        job: JobMode,
+    ) -> Self {
+        CycleData {
+            controller_id,
+            data,
+            timestamp,
+            state: StateValues::new(op, job),
+            options: Default::default(),
+        }
+    }
+
+    /// Create a `ControllerAction` message.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use chrono::DateTime;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_controller_action(
+    ///     ID::from_u32(123),
+    ///     ActionID::new(1014),
+    ///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+    /// );
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// let (id, action, _) = msg.action().unwrap();
+    /// assert_eq!(123, id);
+    /// assert_eq!(ActionCategory::ClampClose, action.category());
+    ///
+    /// let json = msg.to_json_str()?;
+    /// assert!(json.starts_with(r#"{"$type":"ControllerAction","controllerId":123,"actionId":1014,"timestamp":"2019-02-26T02:03:04+08:00","#));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn new_controller_action(
+        controller_id: ID,
+        action_id: ActionID,
+        timestamp: Timestamp<'a>,
+    ) -> Self {
+        ControllerAction { controller_id, action_id, timestamp, options: Default::default() }
+    }
+
+    /// Create a `ControllersList` message from a list of controllers, keyed by their
+    /// controller ID's.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if more than one controller has the same controller ID.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut c1 = Controller::default();
+    /// c1.controller_id = ID::from_u32(1);
+    /// let mut c2 = Controller::default();
+    /// c2.controller_id = ID::from_u32(1);
+    ///
+    /// assert_eq!(
+    ///     "duplicated controller ID: 1",
+    ///     Message::new_controllers_list(vec![c1, c2]).unwrap_err()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let controllers: Vec<_> = [3, 1, 2]
+    ///     .iter()
+    ///     .map(|&id| Controller { controller_id: ID::from_u32(id), ..Default::default() })
+    ///     .collect();
+    ///
+    /// let msg = Message::new_controllers_list(controllers)?;
+    ///
+    /// if let Message::ControllersList { data, .. } = &msg {
+    ///     assert_eq!(vec![3, 1, 2], data.keys().map(|&id| u32::from(id)).collect::<Vec<_>>());
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// let controllers = msg.into_controllers().unwrap();
+    /// assert_eq!(3, controllers.len());
+    /// assert_eq!(1, controllers[1].controller_id);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn new_controllers_list(
+        controllers: impl IntoIterator<Item = Controller<'a>>,
+    ) -> std::result::Result<Self, String> {
+        let mut data = IndexMap::new();
+
+        for c in controllers {
+            let id = c.controller_id;
+
+            if data.insert(id, c).is_some() {
+                return Err(format!("duplicated controller ID: {}", id));
+            }
+        }
+
+        Ok(ControllersList { data, options: Default::default() })
+    }
+
+    /// Error message for replying to a message of the wrong type.
+    fn reply_mismatch(&self, expected: MessageKind) -> String {
+        format!("cannot reply to a {} message - expected {}", self.kind(), expected)
+    }
+
+    /// Create a `ControllersList` message in reply to a `RequestControllersList` message.
+    ///
+    /// If the request is for a particular controller, only that controller (if any) is included
+    /// in the reply.  Otherwise all the controllers are included.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if this message is not a `RequestControllersList` message, or if
+    /// more than one controller has the same controller ID.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let c1 = Controller { controller_id: ID::from_u32(1), ..Default::default() };
+    /// let c2 = Controller { controller_id: ID::from_u32(2), ..Default::default() };
+    ///
+    /// let request = Message::RequestControllersList {
+    ///     controller_id: Some(ID::from_u32(2)),
+    ///     options: Default::default(),
+    /// };
+    ///
+    /// let reply = request.reply_controllers_list(vec![c1.clone(), c2.clone()])?;
+    /// let list = reply.into_controllers().unwrap();
+    /// assert_eq!(1, list.len());
+    /// assert_eq!(2, list[0].controller_id);
+    ///
+    /// let request = Message::RequestControllersList { controller_id: None, options: Default::default() };
+    /// let reply = request.reply_controllers_list(vec![c1.clone(), c2.clone()])?;
+    /// assert_eq!(2, reply.into_controllers().unwrap().len());
+    ///
+    /// assert_eq!(
+    ///     Err("cannot reply to a Alive message - expected RequestControllersList".into()),
+    ///     Message::new_alive().reply_controllers_list(vec![c1]).map(|_| ())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn reply_controllers_list<'b>(
+        &self,
+        controllers: impl IntoIterator<Item = Controller<'b>>,
+    ) -> std::result::Result<Message<'b>, String> {
+        match self {
+            RequestControllersList { controller_id: None, .. } => {
+                Message::new_controllers_list(controllers)
+            }
+            RequestControllersList { controller_id: Some(id), .. } => {
+                let id = *id;
+                Message::new_controllers_list(
+                    controllers.into_iter().filter(|c| c.controller_id == id),
+                )
+            }
+            _ => Err(self.reply_mismatch(MessageKind::RequestControllersList)),
+        }
+    }
+
+    /// Create a `JobCardsList` message in reply to a `RequestJobCardsList` message.
+    ///
+    /// The reply is for the same controller as the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if this message is not a `RequestJobCardsList` message, or if
+    /// more than one job card has the same job card ID.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let request = Message::RequestJobCardsList {
+    ///     controller_id: ID::from_u32(42),
+    ///     options: Default::default(),
+    /// };
+    ///
+    /// let reply = request.reply_job_cards_list(vec![
+    ///     JobCard::try_new("JOB01", "ABC-123", 0, 8000)?,
+    ///     JobCard::try_new("JOB02", "M002", 2000, 10000)?,
+    /// ])?;
+    ///
+    /// if let Message::JobCardsList { controller_id, data, .. } = &reply {
+    ///     assert_eq!(42, *controller_id);
+    ///     assert_eq!(2, data.len());
+    ///     assert_eq!("M002", data["JOB02"].mold_id());
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     Err("duplicated job card ID: JOB01".into()),
+    ///     request.reply_job_cards_list(vec![
+    ///         JobCard::try_new("JOB01", "ABC-123", 0, 8000)?,
+    ///         JobCard::try_new("JOB01", "M002", 2000, 10000)?,
+    ///     ]).map(|_| ())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn reply_job_cards_list<'b>(
+        &self,
+        job_cards: impl IntoIterator<Item = JobCard<'b>>,
+    ) -> std::result::Result<Message<'b>, String> {
+        let controller_id = match self {
+            RequestJobCardsList { controller_id, .. } => *controller_id,
+            _ => return Err(self.reply_mismatch(MessageKind::RequestJobCardsList)),
+        };
+
+        let mut data = IndexMap::new();
+
+        for jc in job_cards {
+            let (key, jc) = jc.into_keyed();
+
+            if data.contains_key(&key) {
+                return Err(format!("duplicated job card ID: {}", key));
+            }
+
+            data.insert(key, jc);
+        }
+
+        Ok(JobCardsList { controller_id, data, options: Default::default() })
+    }
+
+    /// Create a `MoldDataValue` message in reply to a `ReadMoldData` message for a
+    /// particular mold setting.
+    ///
+    /// The reply is for the same controller and mold setting as the request.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if this message is not a `ReadMoldData` message, or if it requests
+    /// the entire set of mold data instead of a particular mold setting.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let request = Message::ReadMoldData {
+    ///     controller_id: ID::from_u32(42),
+    ///     field: Some(TextID::new("Z_QDMLDOPNSPD").unwrap()),
+    ///     options: Default::default(),
+    /// };
+    ///
+    /// let reply = request.reply_mold_data_value(R32::new(123.5))?;
+    ///
+    /// if let Message::MoldDataValue { controller_id, field, value, .. } = &reply {
+    ///     assert_eq!(42, *controller_id);
+    ///     assert_eq!("Z_QDMLDOPNSPD", field);
+    ///     assert_eq!(123.5, value.raw());
+    /// } else {
+    ///     panic!();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn reply_mold_data_value(&self, value: R32) -> std::result::Result<Message<'_>, String> {
+        match self {
+            ReadMoldData { controller_id, field: Some(field), .. } => Ok(MoldDataValue {
+                controller_id: *controller_id,
+                field: field.clone(),
+                value,
+                options: Default::default(),
+            }),
+            ReadMoldData { field: None, .. } => {
+                Err("cannot reply to a ReadMoldData message for the entire set of mold data".into())
+            }
+            _ => Err(self.reply_mismatch(MessageKind::ReadMoldData)),
+        }
+    }
+
+    /// Create an `OperatorInfo` message in reply to a `LoginOperator` message.
+    ///
+    /// The reply is for the same controller and password as the request.
+    /// Set `operator_id` to `None` if the password is not recognized.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if this message is not a `LoginOperator` message, or if the
+    /// reply is invalid (see [`new_operator_info`]).
+    ///
+    /// [`new_operator_info`]: enum.Message.html#method.new_operator_info
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"LoginOperator","controllerId":123,"password":"secret!","sequence":1}"#;
+    /// let request = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// let reply = request.reply_operator_info(Some(ID::from_u32(42)), "John", 5)?;
+    ///
+    /// if let Message::OperatorInfo { controller_id, operator_id, name, password, level, .. } = &reply {
+    ///     assert_eq!(123, *controller_id);
+    ///     assert_eq!(Some(ID::from_u32(42)), *operator_id);
+    ///     assert_eq!("John", name);
+    ///     assert_eq!("secret!", password);
+    ///     assert_eq!(5, *level);
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     Err("level 99 is too high - must be between 0 and 10".into()),
+    ///     request.reply_operator_info(Some(ID::from_u32(42)), "John", 99).map(|_| ())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn reply_operator_info<'b>(
+        &'b self,
+        operator_id: Option<ID>,
+        name: &'b str,
+        level: u8,
+    ) -> std::result::Result<Message<'b>, String> {
+        match self {
+            LoginOperator { controller_id, password, .. } => {
+                Message::new_operator_info(*controller_id, operator_id, name, password, level)
+            }
+            _ => Err(self.reply_mismatch(MessageKind::LoginOperator)),
+        }
+    }
 
     /// Get the optional message ID from the `options` field.
     pub fn id(&self) -> Option<&str> {
         match self {
-            Alive { options }
-            | ControllerAction { options, .. }
-            | RequestControllersList { options, .. }
-            | ControllersList { options, .. }
-            | ControllerStatus { options, .. }
-            | CycleData { options, .. }
-            | RequestJobCardsList { options, .. }
-            | JobCardsList { options, .. }
-            | Join { options, .. }
-            | JoinResponse { options, .. }
-            | RequestMoldData { options, .. }
-            | MoldData { options, .. }
-            | ReadMoldData { options, .. }
-            | MoldDataValue { options, .. }
-            | LoginOperator { options, .. }
-            | OperatorInfo { options, .. } => options.id(),
+            Alive { options }
+            | ControllerAction { options, .. }
+            | RequestControllersList { options, .. }
+            | ControllersList { options, .. }
+            | ControllerStatus { options, .. }
+            | CycleData { options, .. }
+            | RequestJobCardsList { options, .. }
+            | JobCardsList { options, .. }
+            | Join { options, .. }
+            | JoinResponse { options, .. }
+            | RequestMoldData { options, .. }
+            | MoldData { options, .. }
+            | ReadMoldData { options, .. }
+            | MoldDataValue { options, .. }
+            | LoginOperator { options, .. }
+            | OperatorInfo { options, .. } => options.id(),
+        }
+    }
+
+    /// Set the message ID in the `options` field, e.g. to correlate a request with its response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the ID string is empty or all-whitespace.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(
+    ///     "invalid value: a non-empty, non-whitespace, all-ASCII string required",
+    ///     Message::new_alive().with_id("  ").unwrap_err()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::RequestMoldData {
+    ///     controller_id: ID::from_u32(123),
+    ///     options: Default::default(),
+    /// };
+    /// assert_eq!(None, msg.id());
+    ///
+    /// let msg = msg.with_id("req-42")?;
+    /// assert_eq!(Some("req-42"), msg.id());
+    /// assert_eq!(
+    ///     format!(r#"{{"$type":"RequestMoldData","controllerId":123,"id":"req-42","sequence":{}}}"#, msg.sequence()),
+    ///     msg.to_json_str()?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn with_id(mut self, id: &'a str) -> std::result::Result<Self, String> {
+        self.options_mut().set_id(id)?;
+        Ok(self)
+    }
+
+    /// Clone the `Message`, assigning a new sequence number to the clone.
+    ///
+    /// This is useful for sending multiple copies of a template message.
+    /// Notice that `clone` keeps the same sequence number.
+    ///
+    /// With the `manual-sequence` feature, the sequence number of the clone is set to 0.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut options = MessageOptions::new_with_priority(10);
+    /// options.set_sequence(42);
+    /// let msg = Message::Alive { options };
+    ///
+    /// let dup = msg.duplicate();
+    /// assert_ne!(42, dup.sequence());
+    /// assert_eq!(10, dup.priority());
+    ///
+    /// assert_eq!(msg.sequence(), msg.clone().sequence());
+    /// ~~~
+    pub fn duplicate(&self) -> Self {
+        let mut msg = self.clone();
+        msg.options_mut().set_sequence(next_sequence());
+        msg
+    }
+
+    // Get a mutable reference to the `options` field.
+    fn options_mut(&mut self) -> &mut MessageOptions<'a> {
+        match self {
+            Alive { options }
+            | ControllerAction { options, .. }
+            | RequestControllersList { options, .. }
+            | ControllersList { options, .. }
+            | ControllerStatus { options, .. }
+            | CycleData { options, .. }
+            | RequestJobCardsList { options, .. }
+            | JobCardsList { options, .. }
+            | Join { options, .. }
+            | JoinResponse { options, .. }
+            | RequestMoldData { options, .. }
+            | MoldData { options, .. }
+            | ReadMoldData { options, .. }
+            | MoldDataValue { options, .. }
+            | LoginOperator { options, .. }
+            | OperatorInfo { options, .. } => options,
+        }
+    }
+
+    /// Get the message sequence number from the `options` field.
+    pub fn sequence(&self) -> u64 {
+        match self {
+            Alive { options }
+            | ControllerAction { options, .. }
+            | RequestControllersList { options, .. }
+            | ControllersList { options, .. }
+            | ControllerStatus { options, .. }
+            | CycleData { options, .. }
+            | RequestJobCardsList { options, .. }
+            | JobCardsList { options, .. }
+            | Join { options, .. }
+            | JoinResponse { options, .. }
+            | RequestMoldData { options, .. }
+            | MoldData { options, .. }
+            | ReadMoldData { options, .. }
+            | MoldDataValue { options, .. }
+            | LoginOperator { options, .. }
+            | OperatorInfo { options, .. } => options.sequence(),
+        }
+    }
+
+    /// Get the message priority from the `options` field.
+    pub fn priority(&self) -> i32 {
+        match self {
+            Alive { options, .. }
+            | ControllerAction { options, .. }
+            | RequestControllersList { options, .. }
+            | ControllersList { options, .. }
+            | ControllerStatus { options, .. }
+            | CycleData { options, .. }
+            | RequestJobCardsList { options, .. }
+            | JobCardsList { options, .. }
+            | Join { options, .. }
+            | JoinResponse { options, .. }
+            | RequestMoldData { options, .. }
+            | MoldData { options, .. }
+            | ReadMoldData { options, .. }
+            | MoldDataValue { options, .. }
+            | LoginOperator { options, .. }
+            | OperatorInfo { options, .. } => options.priority(),
+        }
+    }
+
+    /// Get the ID of the controller that the `Message` refers to (if any).
+    ///
+    /// Returns `None` for message types not related to a particular controller,
+    /// or for a `RequestControllersList` message requesting all controllers.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_operator_info(ID::from_u32(42), None, "John", "MyPassword", 5)?;
+    /// assert_eq!(Some(ID::from_u32(42)), msg.controller_id());
+    /// assert_eq!(None, Message::new_alive().controller_id());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn controller_id(&self) -> Option<ID> {
+        match self {
+            ControllerAction { controller_id, .. }
+            | ControllerStatus { controller_id, .. }
+            | CycleData { controller_id, .. }
+            | RequestJobCardsList { controller_id, .. }
+            | JobCardsList { controller_id, .. }
+            | RequestMoldData { controller_id, .. }
+            | MoldData { controller_id, .. }
+            | ReadMoldData { controller_id, .. }
+            | MoldDataValue { controller_id, .. }
+            | LoginOperator { controller_id, .. }
+            | OperatorInfo { controller_id, .. } => Some(*controller_id),
+            RequestControllersList { controller_id, .. } => *controller_id,
+            Alive { .. } | ControllersList { .. } | Join { .. } | JoinResponse { .. } => None,
+        }
+    }
+
+    /// Get the IDs of all the controllers that the `Message` refers to.
+    ///
+    /// This is the same as [`controller_id`] (if any), except for a `ControllersList` message,
+    /// which refers to all the controllers in the list (in order).
+    ///
+    /// [`controller_id`]: enum.Message.html#method.controller_id
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_operator_info(ID::from_u32(42), None, "John", "MyPassword", 5)?;
+    /// assert_eq!(vec![ID::from_u32(42)], msg.controller_ids_referenced());
+    /// assert!(Message::new_alive().controller_ids_referenced().is_empty());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn controller_ids_referenced(&self) -> Vec<ID> {
+        match self {
+            ControllersList { data, .. } => data.keys().copied().collect(),
+            _ => self.controller_id().into_iter().collect(),
+        }
+    }
+
+    /// Get the language of a `Join` message.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::All);
+    /// assert_eq!(Some(Language::EN), msg.language());
+    /// assert_eq!(None, Message::new_alive().language());
+    /// ~~~
+    pub fn language(&self) -> Option<Language> {
+        match self {
+            Join { language, .. } => Some(*language),
+            _ => None,
+        }
+    }
+
+    /// Get the type of the `Message`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert_eq!(MessageKind::Alive, Message::new_alive().kind());
+    /// assert_eq!(MessageKind::Join, Message::new_join("hello", Filters::All).kind());
+    ///
+    /// let json = r#"{"$type":"JoinResponse","result":100,"level":5,"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert_eq!(MessageKind::JoinResponse, msg.kind());
+    /// assert_eq!("JoinResponse", msg.kind().as_str());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Alive { .. } => MessageKind::Alive,
+            ControllerAction { .. } => MessageKind::ControllerAction,
+            RequestControllersList { .. } => MessageKind::RequestControllersList,
+            ControllersList { .. } => MessageKind::ControllersList,
+            ControllerStatus { .. } => MessageKind::ControllerStatus,
+            CycleData { .. } => MessageKind::CycleData,
+            RequestJobCardsList { .. } => MessageKind::RequestJobCardsList,
+            JobCardsList { .. } => MessageKind::JobCardsList,
+            Join { .. } => MessageKind::Join,
+            JoinResponse { .. } => MessageKind::JoinResponse,
+            RequestMoldData { .. } => MessageKind::RequestMoldData,
+            MoldData { .. } => MessageKind::MoldData,
+            ReadMoldData { .. } => MessageKind::ReadMoldData,
+            MoldDataValue { .. } => MessageKind::MoldDataValue,
+            LoginOperator { .. } => MessageKind::LoginOperator,
+            OperatorInfo { .. } => MessageKind::OperatorInfo,
+        }
+    }
+
+    /// Returns true if the message is an `Alive` (keep-alive) message.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(Message::new_alive().is_keepalive());
+    /// assert!(!Message::new_join("MyPassword", Filters::All).is_keepalive());
+    /// ~~~
+    pub fn is_keepalive(&self) -> bool {
+        matches!(self, Alive { .. })
+    }
+
+    /// Get the reply to an `Alive` (keep-alive) message, which is a new `Alive` message
+    /// (with its own sequence number).
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"Alive","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// let reply = msg.keepalive_reply().unwrap();
+    /// assert!(reply.is_keepalive());
+    ///
+    /// assert!(Message::new_join("MyPassword", Filters::All).keepalive_reply().is_none());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn keepalive_reply(&self) -> Option<Message<'static>> {
+        if self.is_keepalive() {
+            Some(Message::new_alive())
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the message must be answered by the MIS/MES integrator with a reply
+    /// message (i.e. the message type documents an *Action Required*).
+    ///
+    /// * [`RequestJobCardsList`] must be answered with a [`JobCardsList`] message.
+    /// * [`LoginOperator`] must be answered with an [`OperatorInfo`] message.
+    ///
+    /// [`RequestJobCardsList`]: enum.Message.html#variant.RequestJobCardsList
+    /// [`JobCardsList`]: enum.Message.html#variant.JobCardsList
+    /// [`LoginOperator`]: enum.Message.html#variant.LoginOperator
+    /// [`OperatorInfo`]: enum.Message.html#variant.OperatorInfo
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"LoginOperator","controllerId":123,"password":"secret!","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(msg.requires_mis_response());
+    ///
+    /// let json = r#"{"$type":"RequestJobCardsList","controllerId":123,"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(msg.requires_mis_response());
+    ///
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(!msg.requires_mis_response());
+    ///
+    /// assert!(!Message::new_alive().requires_mis_response());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn requires_mis_response(&self) -> bool {
+        matches!(self, RequestJobCardsList { .. } | LoginOperator { .. })
+    }
+
+    /// Get the organization ID of a `Join` message.
+    ///
+    /// Returns `None` for all other message types, or if the `Join` message has no organization ID.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::All, "MyCompany")?;
+    /// assert_eq!(Some("MyCompany"), msg.org_id());
+    ///
+    /// assert_eq!(None, Message::new_join("MyPassword", Filters::All).org_id());
+    /// assert_eq!(None, Message::new_alive().org_id());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn org_id(&self) -> Option<&str> {
+        match self {
+            Join { org_id, .. } => org_id.as_ref().map(|id| id.get()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the message is a `ControllerStatus` message signaling that a controller
+    /// has just connected (i.e. its `controller` field is set).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05"},"controller":{"controllerId":123,"displayName":"Testing","controllerType":"Ai02","version":"2.2","model":"JM138Ai","IP":"192.168.1.1:12345","opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(msg.is_initial_connect());
+    /// assert!(!msg.is_disconnect_event());
+    ///
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"opMode":"Manual","state":{"opMode":"Manual","jobMode":"ID05"},"sequence":2}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(!msg.is_initial_connect());
+    ///
+    /// assert!(!Message::new_alive().is_initial_connect());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn is_initial_connect(&self) -> bool {
+        matches!(self, ControllerStatus { controller: Some(_), .. })
+    }
+
+    /// Returns true if the message is a `ControllerStatus` message signaling that a controller
+    /// has disconnected (i.e. its `is_disconnected` field is `Some(true)`).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"isDisconnected":true,"opMode":"Offline","jobMode":"Offline","state":{"opMode":"Offline","jobMode":"Offline"},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(msg.is_disconnect_event());
+    /// assert!(!msg.is_initial_connect());
+    ///
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"isDisconnected":false,"state":{"opMode":"Manual","jobMode":"ID05"},"sequence":2}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert!(!msg.is_disconnect_event());
+    ///
+    /// assert!(!Message::new_alive().is_disconnect_event());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn is_disconnect_event(&self) -> bool {
+        matches!(self, ControllerStatus { is_disconnected: Some(true), .. })
+    }
+
+    /// Get whether the operator has logged out in a `ControllerStatus` message.
+    ///
+    /// Returns `Some(true)` if `operator_id` is `Some(None)` (i.e. the user has logged out,
+    /// serialized as `0`), `Some(false)` if `operator_id` is `Some(Some(id))`, and `None`
+    /// if `operator_id` is not relevant or the message is of another type.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"operatorId":0,"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert_eq!(Some(true), msg.operator_logged_out());
+    ///
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"operatorId":42,"state":{"opMode":"Automatic","jobMode":"ID05","operatorId":42},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert_eq!(Some(false), msg.operator_logged_out());
+    ///
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// assert_eq!(None, msg.operator_logged_out());
+    ///
+    /// assert_eq!(None, Message::new_alive().operator_logged_out());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn operator_logged_out(&self) -> Option<bool> {
+        match self {
+            ControllerStatus { operator_id: Some(id), .. } => Some(id.is_none()),
+            _ => None,
+        }
+    }
+
+    /// Get the name of the mold setting of a `ReadMoldData` or `MoldDataValue` message.
+    ///
+    /// Returns `None` for all other message types, or if the `ReadMoldData` message
+    /// reads all mold settings.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::MoldDataValue {
+    ///     controller_id: ID::from_u32(123),
+    ///     field: TextID::new("Z_SDINJSPD01").unwrap(),
+    ///     value: R32::new(42.0),
+    ///     options: Default::default(),
+    /// };
+    /// assert_eq!(Some("Z_SDINJSPD01"), msg.setting_name());
+    ///
+    /// let msg = Message::ReadMoldData {
+    ///     controller_id: ID::from_u32(123),
+    ///     field: None,
+    ///     options: Default::default(),
+    /// };
+    /// assert_eq!(None, msg.setting_name());
+    ///
+    /// assert_eq!(None, Message::new_alive().setting_name());
+    /// ~~~
+    pub fn setting_name(&self) -> Option<&str> {
+        match self {
+            ReadMoldData { field: Some(field), .. } | MoldDataValue { field, .. } => {
+                Some(field.get())
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the timestamp of a `ControllerAction`, `CycleData` or `MoldData` message.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!("2019-02-26T02:03:04+08:00", msg.timestamp().unwrap().to_rfc3339());
+    /// assert_eq!(None, Message::new_alive().timestamp());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn timestamp(&self) -> Option<Timestamp<'_>> {
+        match self {
+            #[cfg(feature = "chrono")]
+            ControllerAction { timestamp, .. }
+            | CycleData { timestamp, .. }
+            | MoldData { timestamp, .. } => Some(*timestamp),
+            //
+            #[cfg(not(feature = "chrono"))]
+            ControllerAction { timestamp, .. }
+            | CycleData { timestamp, .. }
+            | MoldData { timestamp, .. } => Some(timestamp.as_borrowed()),
+            //
+            _ => None,
+        }
+    }
+
+    /// Get the timestamp of a `ControllerAction`, `CycleData` or `MoldData` message in UTC.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!("2019-02-25T18:03:04+00:00", msg.timestamp_utc().unwrap().to_rfc3339());
+    /// assert_eq!(None, Message::new_alive().timestamp_utc());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self) -> Option<DateTime<Utc>> {
+        self.timestamp().map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Get the controller ID, action ID and timestamp of a `ControllerAction` message.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"ControllerAction","controllerId":123,"actionId":2001,"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// let (id, action, time) = msg.action().unwrap();
+    /// assert_eq!(123, id);
+    /// assert_eq!(2001, *action);
+    /// assert_eq!("2019-02-26T02:03:04+08:00", time.to_rfc3339());
+    ///
+    /// assert_eq!(None, Message::new_alive().action());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn action(&self) -> Option<(ID, ActionID, Timestamp<'_>)> {
+        match self {
+            #[cfg(feature = "chrono")]
+            ControllerAction { controller_id, action_id, timestamp, .. } => {
+                Some((*controller_id, *action_id, *timestamp))
+            }
+            //
+            #[cfg(not(feature = "chrono"))]
+            ControllerAction { controller_id, action_id, timestamp, .. } => {
+                Some((*controller_id, *action_id, timestamp.as_borrowed()))
+            }
+            //
+            _ => None,
+        }
+    }
+
+    /// Get the value of a field in the `data` dictionary of a `CycleData` or `MoldData` message
+    /// as an `f32`.
+    ///
+    /// Returns `None` if the field does not exist or the message is of another type.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.5},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!(Some(12.5), msg.get_value_f32("Z_QDCYCTIM"));
+    /// assert_eq!(None, msg.get_value_f32("Z_QDINJTIM"));
+    /// assert_eq!(None, Message::new_alive().get_value_f32("Z_QDCYCTIM"));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn get_value_f32(&self, field: &str) -> Option<f32> {
+        match self {
+            CycleData { data, .. } | MoldData { data, .. } => data.get(field).map(|v| v.raw()),
+            _ => None,
+        }
+    }
+
+    /// Get the data dictionary of a `CycleData` or `MoldData` message, or the last cycle data
+    /// of all the controllers in a `ControllersList` message, as an ordered list of
+    /// (key, value) pairs.
+    ///
+    /// The pairs are in the same order as in the data dictionaries.  For a `ControllersList`
+    /// message, the cycle data of each controller follows in the order of the controllers.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.5,"Z_QDINJTIM":3},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!(
+    ///     Some(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.5), ("Z_QDINJTIM", 3.0)]),
+    ///     msg.data_pairs()
+    /// );
+    /// assert_eq!(None, Message::new_alive().data_pairs());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn data_pairs(&self) -> Option<Vec<(&str, f32)>> {
+        match self {
+            CycleData { data, .. } | MoldData { data, .. } => {
+                Some(data.iter().map(|(k, v)| (k.get(), v.raw())).collect())
+            }
+            ControllersList { data, .. } => {
+                Some(data.values().flat_map(|c| c.cycle_data_pairs()).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the keys of the data dictionary of a `CycleData` or `MoldData` message, in order.
+    ///
+    /// Returns `None` for all other message types.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.5,"Z_QDINJTIM":3},"timestamp":"2019-02-26T02:03:04+08:00","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// assert_eq!(Some(vec!["Z_QDGODCNT", "Z_QDCYCTIM", "Z_QDINJTIM"]), msg.data_keys());
+    /// assert_eq!(None, Message::new_alive().data_keys());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn data_keys(&self) -> Option<Vec<&str>> {
+        match self {
+            CycleData { data, .. } | MoldData { data, .. } => {
+                Some(data.keys().map(|k| k.get()).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Split a `ControllersList` message into one `ControllerStatus` message per controller.
+    ///
+    /// Each `ControllerStatus` message has its `controller` field set to the controller's data,
+    /// its `state` field set to the controller's current states, and all other info fields
+    /// set to `None`.  Each message gets a new sequence number.
+    ///
+    /// Returns `None` if the message is not a `ControllersList`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c1 = Controller::default();
+    /// c1.controller_id = ID::from_u32(1);
+    /// let mut c2 = Controller::default();
+    /// c2.controller_id = ID::from_u32(2);
+    /// c2.op_mode = OpMode::Automatic;
+    /// c2.job_mode = JobMode::ID02;
+    ///
+    /// let msg = Message::ControllersList {
+    ///     data: vec![(c1.controller_id, c1), (c2.controller_id, c2)].into_iter().collect(),
+    ///     options: Default::default(),
+    /// };
+    ///
+    /// let list = msg.explode_controllers_list().unwrap();
+    /// assert_eq!(2, list.len());
+    ///
+    /// if let Message::ControllerStatus { controller_id, state, controller: Some(c), .. } = &list[1] {
+    ///     assert_eq!(2, *controller_id);
+    ///     assert_eq!(OpMode::Automatic, state.op_mode());
+    ///     assert_eq!(JobMode::ID02, c.job_mode);
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert!(Message::new_alive().explode_controllers_list().is_none());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn explode_controllers_list(&self) -> Option<Vec<Message<'_>>> {
+        if let ControllersList { data, .. } = self {
+            Some(
+                data.values()
+                    .map(|c| ControllerStatus {
+                        controller_id: c.controller_id,
+                        display_name: None,
+                        is_disconnected: None,
+                        op_mode: None,
+                        job_mode: None,
+                        alarm: None,
+                        audit: None,
+                        variable: None,
+                        operator_id: None,
+                        operator_name: None,
+                        job_card_id: None,
+                        mold_id: None,
+                        state: c.to_state_values(),
+                        controller: Some(Box::new(c.clone())),
+                        options: MessageOptions::new(),
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Convert a `ControllersList` message into the list of controllers it contains,
+    /// in the same order.
+    ///
+    /// Returns `None` if the message is not a `ControllersList`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut c = Controller::default();
+    /// c.controller_id = ID::from_u32(42);
+    ///
+    /// let msg = Message::new_controllers_list(vec![c])?;
+    /// let controllers = msg.into_controllers().unwrap();
+    /// assert_eq!(1, controllers.len());
+    /// assert_eq!(42, controllers[0].controller_id);
+    ///
+    /// assert!(Message::new_alive().into_controllers().is_none());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn into_controllers(self) -> Option<Vec<Controller<'a>>> {
+        match self {
+            ControllersList { data, .. } => Some(data.into_iter().map(|(_, c)| c).collect()),
+            _ => None,
+        }
+    }
+
+    /// Re-borrow a `Message` with its lifetime shortened to that of the borrow.
+    ///
+    /// `Message` is covariant over its lifetime, so a `&'s Message<'a>` can always be used
+    /// as a `&'s Message<'s>` without cloning.  The compiler normally does this automatically,
+    /// but this method makes the conversion explicit for cases where it is not inferred
+    /// (e.g. when storing references to messages of different lifetimes in the same collection,
+    /// or when passing a message to a callback that requires matching lifetimes).
+    ///
+    /// It is not possible to produce an _owned_ `Message<'_>` that borrows from `self`
+    /// without cloning, because the `Box` and `IndexMap` fields own their allocations.
+    /// Use `clone` for that.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// // A short-lived callback that keeps references to the messages passed to it
+    /// fn forward<'s>(msg: &'s Message<'s>, sink: &mut Vec<&'s Message<'s>>) {
+    ///     sink.push(msg);
+    /// }
+    ///
+    /// let json = String::from(r#"{"$type":"Join","version":"1.0.0","password":"hello","language":"EN","filter":"All","sequence":1}"#);
+    /// let msg1 = Message::parse_from_json_str(&json).map_err(|err| err.to_string())?;
+    /// let msg2: Message<'static> = Message::new_alive();
+    ///
+    /// let mut sink = Vec::new();
+    /// forward(msg1.reborrow(), &mut sink);
+    /// forward(msg2.reborrow(), &mut sink);
+    ///
+    /// assert_eq!(2, sink.len());
+    /// assert_eq!(1, sink[0].sequence());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn reborrow(&self) -> &Message<'_> {
+        self
+    }
+
+    /// Create a copy of the `Message` with all passwords replaced by [`REDACTED_PASSWORD`],
+    /// suitable for logging.
+    ///
+    /// The `password` fields of `Join`, `LoginOperator` and `OperatorInfo` messages are redacted.
+    /// All other fields are kept intact.
+    ///
+    /// [`REDACTED_PASSWORD`]: enum.Message.html#associatedconstant.REDACTED_PASSWORD
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "manual-sequence"), doc = " ~~~")]
+    #[cfg_attr(feature = "manual-sequence", doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+    /// let redacted = msg.redact();
+    ///
+    /// assert_eq!(
+    ///     r#"{"$type":"Join","orgId":"MyCompany","version":"4.0","password":"********","language":"EN","filter":"Status, Cycle","sequence":1}"#,
+    ///     redacted.to_json_str()?
+    /// );
+    ///
+    /// // The original message is not touched
+    /// if let Message::Join { password, .. } = msg {
+    ///     assert_eq!("MyPassword", password);
+    /// } else {
+    ///     panic!();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn redact(&self) -> Self {
+        let mut msg = self.clone();
+
+        match msg {
+            Join { ref mut password, .. } | LoginOperator { ref mut password, .. } => {
+                *password = Self::REDACTED_PASSWORD.into();
+            }
+            OperatorInfo { ref mut password, .. } => {
+                *password = TextName::new_from_str(Self::REDACTED_PASSWORD).unwrap();
+            }
+            _ => (),
         }
+
+        msg
     }
 
-    /// Get the message sequence number from the `options` field.
-    pub fn sequence(&self) -> u64 {
-        match self {
-            Alive { options }
-            | ControllerAction { options, .. }
-            | RequestControllersList { options, .. }
-            | ControllersList { options, .. }
-            | ControllerStatus { options, .. }
-            | CycleData { options, .. }
-            | RequestJobCardsList { options, .. }
-            | JobCardsList { options, .. }
-            | Join { options, .. }
-            | JoinResponse { options, .. }
-            | RequestMoldData { options, .. }
-            | MoldData { options, .. }
-            | ReadMoldData { options, .. }
-            | MoldDataValue { options, .. }
-            | LoginOperator { options, .. }
-            | OperatorInfo { options, .. } => options.sequence(),
+    /// Remove large volatile data (i.e. `last_cycle_data` and `variables`) from the controllers
+    /// embedded in a `ControllerStatus` or `ControllersList` message, keeping all other fields.
+    ///
+    /// This is useful for keeping archived messages compact.
+    /// All other message types are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let json = r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05"},"controller":{"controllerId":123,"displayName":"Testing","controllerType":"Ai02","version":"2.2","model":"JM138Ai","IP":"192.168.1.1:12345","opMode":"Automatic","jobMode":"ID05","lastCycleData":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.33},"variables":{"Z_QDNOZTEMP":230}},"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    ///
+    /// let msg = msg.sanitize_for_storage();
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// if let Message::ControllerStatus { controller: Some(c), .. } = &msg {
+    ///     assert!(c.last_cycle_data.is_empty());
+    ///     assert!(c.variables.is_empty());
+    ///     assert_eq!(123, c.controller_id);
+    ///     assert_eq!("Testing", &c.display_name);
+    ///     assert_eq!(JobMode::ID05, c.job_mode);
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05"},"controller":{"controllerId":123,"displayName":"Testing","controllerType":"Ai02","version":"2.2","model":"JM138Ai","IP":"192.168.1.1:12345","opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#,
+    ///     msg.to_json_str()?
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn sanitize_for_storage(mut self) -> Self {
+        fn sanitize(c: &mut Controller<'_>) {
+            c.last_cycle_data = Default::default();
+            c.variables = Default::default();
         }
-    }
 
-    /// Get the message priority from the `options` field.
-    pub fn priority(&self) -> i32 {
         match self {
-            Alive { options, .. }
-            | ControllerAction { options, .. }
-            | RequestControllersList { options, .. }
-            | ControllersList { options, .. }
-            | ControllerStatus { options, .. }
-            | CycleData { options, .. }
-            | RequestJobCardsList { options, .. }
-            | JobCardsList { options, .. }
-            | Join { options, .. }
-            | JoinResponse { options, .. }
-            | RequestMoldData { options, .. }
-            | MoldData { options, .. }
-            | ReadMoldData { options, .. }
-            | MoldDataValue { options, .. }
-            | LoginOperator { options, .. }
-            | OperatorInfo { options, .. } => options.priority(),
+            ControllerStatus { controller: Some(ref mut c), .. } => sanitize(c),
+            ControllersList { ref mut data, .. } => data.values_mut().for_each(sanitize),
+            _ => (),
         }
+
+        self
     }
 
     /// Validate the `Message` data structure.
@@ -911,14 +3143,13 @@ impl<'a> Message<'a> {
             | JoinResponse { .. }
             | RequestMoldData { .. }
             | ControllersList { .. }
-            | CycleData { .. }
             | ReadMoldData { .. }
-            | MoldDataValue { .. }
             | LoginOperator { .. }
             | JobCardsList { .. }
             | MoldData { .. } => (),
 
             ControllerStatus {
+                controller_id,
                 display_name,
                 is_disconnected,
                 op_mode,
@@ -934,6 +3165,9 @@ impl<'a> Message<'a> {
                 controller,
                 ..
             } => {
+                validate_offline_consistency(state.op_mode(), state.job_mode())
+                    .map_err(|err| Error::ConstraintViolated(err.into()))?;
+
                 if let Some(c) = controller {
                     // If controller is present, some fields must be None
                     if !is_disconnected.is_none()
@@ -947,6 +3181,9 @@ impl<'a> Message<'a> {
                     }
 
                     // Check controller fields with specified fields
+                    if c.controller_id != *controller_id {
+                        return Err(Error::InconsistentField("controller_id"));
+                    }
                     if display_name.is_some()
                         && display_name.as_ref().unwrap().get() != &c.display_name
                     {
@@ -954,7 +3191,7 @@ impl<'a> Message<'a> {
                     }
                     if operator_name.is_some()
                         && operator_name.as_ref().unwrap().as_ref().map(|x| x.get())
-                            != c.operator.as_ref().map(|u| u.name()).flatten()
+                            != c.operator.as_ref().and_then(|u| u.name())
                     {
                         return Err(Error::InconsistentField("operator_name"));
                     }
@@ -973,7 +3210,7 @@ impl<'a> Message<'a> {
                         return Err(Error::InconsistentState("job_card_id"));
                     }
                     if state.mold_id() != c.mold_id.as_ref().map(|x| x.as_ref().as_ref()) {
-                        return Err(Error::InconsistentField("mold_id"));
+                        return Err(Error::InconsistentState("mold_id"));
                     }
                 }
 
@@ -998,6 +3235,22 @@ impl<'a> Message<'a> {
                 {
                     return Err(Error::InconsistentState("mold_id"));
                 }
+
+                // An operator name requires a logged-on operator
+                if let Some(Some(_)) = operator_name {
+                    if state.operator_id().is_none() {
+                        return Err(Error::InconsistentState("operator_name"));
+                    }
+                }
+            }
+
+            CycleData { data, state, .. } => {
+                if data.is_empty() {
+                    return Err(Error::EmptyField("data"));
+                }
+
+                validate_offline_consistency(state.op_mode(), state.job_mode())
+                    .map_err(|err| Error::ConstraintViolated(err.into()))?;
             }
 
             Join { language, .. } => {
@@ -1011,6 +3264,14 @@ impl<'a> Message<'a> {
                 }
             }
 
+            MoldDataValue { value, .. } => {
+                check_f32(value.raw()).map_err(|err| Error::InvalidField {
+                    field: "value",
+                    value: value.to_string().into(),
+                    description: err.into(),
+                })?;
+            }
+
             OperatorInfo { level, .. } => {
                 if *level > Self::MAX_OPERATOR_LEVEL {
                     return Err(Error::ConstraintViolated(
@@ -1027,6 +3288,165 @@ impl<'a> Message<'a> {
 
         Ok(())
     }
+
+    /// Validate the `Message` data structure, with additional checks for likely mistakes.
+    ///
+    /// In addition to all the checks in [`validate`], the following are rejected:
+    ///
+    /// * A `Join` message with a `filter` that does not subscribe to any message (e.g.
+    ///   `Filters::None`, or only `Filters::OPCUA`).
+    ///   Such a `Join` message is still valid under [`validate`] (for example, to connect
+    ///   without receiving any message), and can be created by [`new_join`] as usual, so
+    ///   there is no separate constructor for it.
+    /// * A `ReadMoldData` or `MoldDataValue` message with a mold setting name that is longer
+    ///   than [`MAX_SETTING_NAME_LEN`] characters or contains white-spaces.  Such names are
+    ///   not used by iChen, but they are not forbidden by the protocol.
+    ///
+    /// [`validate`]: enum.Message.html#method.validate
+    /// [`new_join`]: enum.Message.html#method.new_join
+    /// [`MAX_SETTING_NAME_LEN`]: enum.Message.html#associatedconstant.MAX_SETTING_NAME_LEN
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError`]`)` if some fields in the `Message` are not valid.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::None);
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// assert_eq!(
+    ///     r#"value [None] is invalid for the field filter - must subscribe to at least one of: Status, Cycle, Mold, Actions, Alarms, Audit, JobCards, Operators"#,
+    ///     msg.validate_strict().unwrap_err().to_string()
+    /// );
+    ///
+    /// let msg = Message::new_join("MyPassword", Filters::OPCUA);
+    /// assert_eq!(Ok(()), msg.validate());
+    ///
+    /// assert_eq!(
+    ///     r#"value [OPCUA] is invalid for the field filter - must subscribe to at least one of: Status, Cycle, Mold, Actions, Alarms, Audit, JobCards, Operators"#,
+    ///     msg.validate_strict().unwrap_err().to_string()
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let msg = Message::new_join("MyPassword", Filters::Status | Filters::Cycle);
+    /// assert_eq!(Ok(()), msg.validate_strict());
+    /// ~~~
+    pub fn validate_strict(&self) -> Result<'a, ()> {
+        self.validate()?;
+
+        match self {
+            Join { filter, .. }
+                if !filter.intersects(
+                    Filters::Status
+                        | Filters::Cycle
+                        | Filters::Mold
+                        | Filters::Actions
+                        | Filters::Alarms
+                        | Filters::Audit
+                        | Filters::JobCards
+                        | Filters::Operators,
+                ) =>
+            {
+                return Err(Error::InvalidField {
+                    field: "filter",
+                    value: filter.to_string().into(),
+                    description: "must subscribe to at least one of: Status, Cycle, Mold, Actions, Alarms, Audit, JobCards, Operators".into(),
+                });
+            }
+
+            ReadMoldData { field: Some(field), .. } | MoldDataValue { field, .. } => {
+                if field.len() > Self::MAX_SETTING_NAME_LEN {
+                    return Err(Error::InvalidField {
+                        field: "field",
+                        value: field.get().to_string().into(),
+                        description: format!(
+                            "mold setting name cannot be longer than {} characters",
+                            Self::MAX_SETTING_NAME_LEN
+                        )
+                        .into(),
+                    });
+                }
+                if field.chars().any(char::is_whitespace) {
+                    return Err(Error::InvalidField {
+                        field: "field",
+                        value: field.get().to_string().into(),
+                        description: "mold setting name cannot contain white-spaces".into(),
+                    });
+                }
+            }
+
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Validate the `Message` data structure, returning it if valid.
+    ///
+    /// This is the same as [`validate`], but consumes the `Message` for easy chaining.
+    ///
+    /// [`validate`]: enum.Message.html#method.validate
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError`]`)` if some fields in the `Message` are not valid.
+    ///
+    /// [`OpenProtocolError`]: enum.OpenProtocolError.html
+    ///
+    /// ## Error Examples
+    ///
+    #[cfg_attr(feature = "chrono", doc = " ~~~")]
+    #[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+    /// # use ichen_openprotocol::*;
+    /// # use chrono::DateTime;
+    /// let msg = Message::new_cycle_data_simple(
+    ///     ID::from_u32(123),
+    ///     Default::default(),
+    ///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+    ///     OpMode::Automatic,
+    ///     JobMode::ID02,
+    /// );
+    ///
+    /// assert_eq!(Some(Error::EmptyField("data")), msg.validated().err());
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let msg = Message::new_join("MyPassword", Filters::All).validated().map_err(|err| err.to_string())?;
+    /// assert_eq!(MessageKind::Join, msg.kind());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn validated(self) -> Result<'a, Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+// Check the top-level fields in a JSON string against the known fields of a message type,
+// failing on the first unknown field.
+fn check_unknown_fields(
+    kind: MessageKind,
+    json: &str,
+) -> std::result::Result<(), serde_json::Error> {
+    let fields = raw_fields(json)?;
+
+    match fields.keys().find(|&key| !kind.is_known_field(key)) {
+        Some(key) => Err(serde::de::Error::custom(format!("unknown field `{}`", key))),
+        None => Ok(()),
+    }
 }
 
 // Tests
@@ -1044,6 +3464,237 @@ mod test {
         }
     }
 
+    /// Parse an RFC3339 timestamp string (for testing purposes).
+    #[cfg(feature = "chrono")]
+    fn parse_timestamp(text: &'static str) -> Timestamp<'static> {
+        DateTime::parse_from_rfc3339(text).unwrap()
+    }
+
+    /// Parse an RFC3339 timestamp string (for testing purposes).
+    #[cfg(not(feature = "chrono"))]
+    fn parse_timestamp(text: &'static str) -> Timestamp<'static> {
+        Timestamp::new(text).unwrap()
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    fn test_message_no_chrono_timestamp() -> Result<(), String> {
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23.123+08:00","controllerId":123,"data":{"Z_QDGODCNT":123},"sequence":1}"#;
+
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some("2016-02-26T01:12:23.123+08:00"), msg.timestamp().as_deref());
+        assert_eq!(
+            r#"{"$type":"CycleData","controllerId":123,"data":{"Z_QDGODCNT":123.0},"timestamp":"2016-02-26T01:12:23.123+08:00","sequence":1}"#,
+            msg.to_json_str()?
+        );
+
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26","controllerId":123,"data":{"Z_QDGODCNT":123},"sequence":1}"#;
+        let err = Message::parse_from_json_str(json).unwrap_err().to_string();
+        assert!(err.contains("expected an RFC3339 timestamp, got [2016-02-26]"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_parse_with_max_depth() -> Result<(), String> {
+        let options = ParseOptions::default();
+
+        // Deeply-nested JSON is rejected
+        let nested = format!("{}1{}", "[".repeat(1000), "]".repeat(1000));
+        let json = format!(r#"{{"$type":"Alive","extra":{},"sequence":1}}"#, nested);
+
+        match Message::parse_from_json_str_with_options(&json, &options) {
+            Err(Error::JsonError(err)) => assert!(err.to_string().contains("maximum depth of 16")),
+            result => return Err(format!("Expected depth error, got {:?}", result)),
+        }
+
+        // Brackets inside strings do not count
+        let json = format!(
+            r#"{{"$type":"Join","version":"1.0.0","password":"{}","language":"EN","filter":"All","sequence":1}}"#,
+            "[".repeat(100)
+        );
+        let msg = Message::parse_from_json_str_with_options(&json, &options)
+            .map_err(|x| x.to_string())?;
+        assert_eq!(MessageKind::Join, msg.kind());
+
+        // Real messages are well within the default depth
+        let json = r#"{"$type":"ControllerStatus","controllerId":1,"displayName":"Hello","controller":{"controllerId":1,"displayName":"Hello","controllerType":"Ai01","version":"1.0.0","model":"JM128-Ai","IP":"127.0.0.1:123","opMode":"Automatic","jobMode":"ID02","lastCycleData":{"Z_QDGODCNT":123},"lastConnectionTime":"2016-03-06T23:11:27.1442177+08:00"},"state":{"opMode":"Automatic","jobMode":"ID02"},"sequence":1}"#;
+        Message::parse_from_json_str_with_options(json, &options).map_err(|x| x.to_string())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_cycle_data_with_unknown_field() -> Result<(), String> {
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","opMode":"Automatic","jobMode":"ID02","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.33},"hello":"world","sequence":1}"#;
+
+        // Unknown fields are ignored by default
+        Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        Message::parse_from_json_str_with_options(json, &Default::default())
+            .map_err(|x| x.to_string())?;
+
+        let options = ParseOptions::new().deny_unknown_fields(true);
+
+        match Message::parse_from_json_str_with_options(json, &options) {
+            Err(Error::JsonError(err)) => assert_eq!("unknown field `hello`", err.to_string()),
+            result => return Err(format!("Expected unknown field error, got {:?}", result)),
+        }
+
+        // Known fields with empty values are not unknown
+        let json = r#"{"$type":"CycleData","id":null,"priority":0,"timestamp":"2016-02-26T01:12:23+08:00","opMode":"Automatic","jobMode":"ID02","jobCardId":null,"controllerId":123,"data":{"Z_QDGODCNT":123},"sequence":1}"#;
+        Message::parse_from_json_str_with_options(json, &options).map_err(|x| x.to_string())?;
+
+        // All fields of a message type are known
+        let json = r#"{"$type":"ControllerStatus","timestamp":"2016-04-01T01:12:23+08:00","controllerId":234,"displayName":"M2","opMode":"Manual","jobMode":"Offline","jobCardId":"XYZ","isDisconnected":false,"alarm":{"key":"DOOROPEN","value":true},"audit":{"key":"PRES","value":50.0},"variable":{"key":"Z_QDNOZTEMP","value":230},"operatorId":987,"operatorName":"Johnny","moldId":"ABC123","state":{"opMode":"Manual","jobMode":"Offline","operatorId":987,"jobCardId":"XYZ","moldId":"ABC123"},"id":"abc","sequence":123,"priority":10}"#;
+
+        match Message::parse_from_json_str_with_options(json, &options) {
+            Err(Error::JsonError(err)) => assert_eq!("unknown field `timestamp`", err.to_string()),
+            result => return Err(format!("Expected unknown field error, got {:?}", result)),
+        }
+
+        let json = json.replacen(r#""timestamp":"2016-04-01T01:12:23+08:00","#, "", 1);
+        Message::parse_from_json_str_with_options(&json, &options).map_err(|x| x.to_string())?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "lenient-json")]
+    #[test]
+    fn test_message_lenient_json() -> Result<(), String> {
+        let json = r#"{
+            // Comment
+            "$type": "CycleData", /* Another comment with a, } */
+            "timestamp": "2016-02-26T01:12:23+08:00",
+            "opMode": "Automatic",
+            "jobMode": "ID02",
+            "controllerId": 123,
+            "data": { "Z_QDGODCNT": 123, "Z_QDCYCTIM": 12.33, },
+            "sequence": 1,
+        }"#;
+
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.33)]), msg.data_pairs());
+
+        // Text inside strings is left untouched
+        let json = r#"{"$type":"Join","version":"1.0.0","password":"a,}/*//","language":"EN","filter":"All","sequence":1,}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        if let Join { password, .. } = msg {
+            assert_eq!("a,}/*//", password);
+        } else {
+            return Err(format!("Expected Join, got {:#?}", msg));
+        }
+
+        // Other syntax errors are still errors
+        assert!(Message::parse_from_json_str(r#"{"$type":"Alive","sequence":1"#).is_err());
+
+        // Options are checked against the cleaned-up text
+        let json = r#"{"$type":"Alive","sequence":1, /* comment */ }"#;
+        let options = ParseOptions::new().max_depth(1).deny_unknown_fields(true);
+        Message::parse_from_json_str_with_options(json, &options).map_err(|x| x.to_string())?;
+
+        // ... and reported against the original text
+        let json = "{\n  // Comment\n  \"$type\": /* 類型 */ \"Alive\",\n  \"sequence\": 1,,\n}";
+        assert_eq!(json.len(), strip_lenient_json(json).unwrap().len());
+        let err = Message::parse_from_json_str(json).unwrap_err();
+        assert_eq!(Some((2, 3)), err.json_location());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_parse_with_bom() -> Result<(), String> {
+        let json = "\u{FEFF}{\"$type\":\"Alive\",\"sequence\":42}";
+
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(MessageKind::Alive, msg.kind());
+        assert_eq!(42, msg.sequence());
+
+        let msg = Message::parse_from_json_str_owned(json).map_err(|x| x.to_string())?;
+        assert_eq!(42, msg.sequence());
+
+        let (msg, rest) = Message::parse_first_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(42, msg.sequence());
+        assert_eq!("", rest);
+
+        // Only a leading BOM is allowed
+        assert!(
+            Message::parse_from_json_str("{\"$type\":\"Alive\",\"sequence\":42}\u{FEFF}").is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_join_language() -> Result<(), String> {
+        let json = r#"{"$type":"Join","version":"1.0.0","password":"hello","language":"B5","filter":"All","sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        let language = msg.language().unwrap();
+        assert_eq!(Language::B5, language);
+        assert_eq!("zh-tw", language.as_ref());
+        assert_eq!("䌓體中文", language.to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_with_type_key() -> Result<(), String> {
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","opMode":"Automatic","jobMode":"ID02","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.33},"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        let output = msg.to_json_str_with_type_key("type").map_err(|x| x.to_string())?;
+        assert!(output.starts_with(r#"{"type":"CycleData","controllerId":123,"#), "{}", output);
+        assert!(!output.contains("$type"));
+
+        let msg2 = Message::parse_from_json_str_with_type_key(&output, "type")
+            .map_err(|x| x.to_string())?;
+        assert_eq!(MessageKind::CycleData, msg2.kind());
+        assert_eq!(msg.data_pairs(), msg2.data_pairs());
+        assert_eq!(
+            msg.to_json_str().map_err(|x| x.to_string())?,
+            msg2.to_json_str().map_err(|x| x.to_string())?
+        );
+
+        // The default deserializer does not recognize the custom key
+        assert!(Message::parse_from_json_str(&output).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_cycle_data_with_string_controller_id() -> Result<(), String> {
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":"123","data":{"Z_QDGODCNT":123},"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some(ID::from_u32(123)), msg.controller_id());
+
+        // Always serialized as a number
+        assert!(msg.to_json_str().map_err(|x| x.to_string())?.contains(r#""controllerId":123,"#));
+
+        for id in &[r#""0""#, r#""abc""#, r#""-1""#, r#""""#, "0"] {
+            let json = format!(
+                r#"{{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":{},"data":{{"Z_QDGODCNT":123}},"sequence":1}}"#,
+                id
+            );
+            assert!(Message::parse_from_json_str(&json).is_err(), "{}", id);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "manual-sequence")]
+    #[test]
+    fn test_message_options_manual_sequence() {
+        let opt1 = MessageOptions::new();
+        let opt2: MessageOptions = Default::default();
+        assert_eq!(0, opt1.sequence());
+        assert_eq!(0, opt2.sequence());
+        assert_eq!(0, Message::new_alive().sequence());
+
+        let mut opt = MessageOptions::new();
+        opt.set_sequence(42);
+        assert_eq!(42, opt.sequence());
+    }
+
     #[test]
     fn test_message_alive_to_json() -> Result<(), String> {
         let mut options = MessageOptions::new_with_priority(20);
@@ -1059,6 +3710,40 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_message_mold_data_write_json() -> Result<(), String> {
+        let mut map: IndexMap<TextID, R32> = IndexMap::new();
+
+        map.insert("Hello".try_into().unwrap(), R32::new(123.0));
+        map.insert("World".try_into().unwrap(), R32::new(-987.6543));
+
+        let m1 = MoldData {
+            controller_id: ID::from_u32(123),
+            data: map,
+            timestamp: parse_timestamp("2019-02-26T02:03:04+08:00"),
+            state: StateValues::new(OpMode::Automatic, JobMode::ID01),
+            options: MessageOptions::default_new(),
+        };
+
+        let mut buf: Vec<u8> = Vec::new();
+        m1.write_json(&mut buf).map_err(|x| x.to_string())?;
+
+        let json = String::from_utf8(buf).map_err(|x| x.to_string())?;
+        assert_eq!(m1.to_json_str()?, json);
+
+        let m2 = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+
+        if let MoldData { controller_id, data, state, .. } = &m2 {
+            assert_eq!(123, *controller_id);
+            assert_eq!(vec![("Hello", 123.0), ("World", -987.6543)], m2.data_pairs().unwrap());
+            assert_eq!(2, data.len());
+            assert_eq!(JobMode::ID01, state.job_mode());
+            Ok(())
+        } else {
+            Err(format!("Expected MoldData, got {:#?}", m2))
+        }
+    }
+
     #[test]
     fn test_message_mold_data_to_json() -> Result<(), String> {
         let mut map: IndexMap<TextID, R32> = IndexMap::new();
@@ -1074,8 +3759,7 @@ mod test {
             controller_id: ID::from_u32(123),
             data: map,
 
-            timestamp: DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00")
-                .map_err(|x| x.to_string())?,
+            timestamp: parse_timestamp("2019-02-26T02:03:04+08:00"),
 
             state: StateValues::try_new_with_all(
                 OpMode::SemiAutomatic,
@@ -1106,40 +3790,279 @@ mod test {
     fn test_message_controllers_list_from_json() -> Result<(), String> {
         let json = r#"{"$type":"ControllersList","data":{"12345":{"controllerId":12345,"displayName":"Hello","controllerType":"Ai12","version":"1.0.0","model":"JM128-Ai","IP":"192.168.5.1:123","opMode":"Manual","jobMode":"ID11","lastCycleData":{"Z_QDGODCNT":8567,"Z_QDCYCTIM":979,"Z_QDINJTIM":5450,"Z_QDPLSTIM":7156,"Z_QDINJENDPOS":8449,"Z_QDPLSENDPOS":2212,"Z_QDFLAG":8988,"Z_QDPRDCNT":65500,"Z_QDCOLTIM":4435,"Z_QDMLDOPNTIM":652,"Z_QDMLDCLSTIM":2908,"Z_QDVPPOS":4732,"Z_QDMLDOPNENDPOS":6677,"Z_QDMAXINJSPD":7133,"Z_QDMAXPLSRPM":641,"Z_QDNOZTEMP":6693,"Z_QDTEMPZ01":9964,"Z_QDTEMPZ02":7579,"Z_QDTEMPZ03":4035,"Z_QDTEMPZ04":5510,"Z_QDTEMPZ05":8460,"Z_QDTEMPZ06":9882,"Z_QDBCKPRS":2753,"Z_QDHLDTIM":9936},"lastConnectionTime":"2016-03-06T23:11:27.1442177+08:00"},"22334":{"controllerId":22334,"displayName":"World","controllerType":"Ai01","version":"1.0.0","model":"JM128-Ai","IP":"192.168.5.2:234","opMode":"SemiAutomatic","jobMode":"ID12","lastCycleData":{"Z_QDGODCNT":6031,"Z_QDCYCTIM":7526,"Z_QDINJTIM":4896,"Z_QDPLSTIM":5196,"Z_QDINJENDPOS":1250,"Z_QDPLSENDPOS":8753,"Z_QDFLAG":3314,"Z_QDPRDCNT":65500,"Z_QDCOLTIM":3435,"Z_QDMLDOPNTIM":7854,"Z_QDMLDCLSTIM":4582,"Z_QDVPPOS":7504,"Z_QDMLDOPNENDPOS":7341,"Z_QDMAXINJSPD":7322,"Z_QDMAXPLSRPM":6024,"Z_QDNOZTEMP":3406,"Z_QDTEMPZ01":3067,"Z_QDTEMPZ02":9421,"Z_QDTEMPZ03":2080,"Z_QDTEMPZ04":8845,"Z_QDTEMPZ05":4478,"Z_QDTEMPZ06":3126,"Z_QDBCKPRS":2807,"Z_QDHLDTIM":3928},"lastConnectionTime":"2016-03-06T23:11:27.149218+08:00"}},"sequence":68568}"#;
 
-        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
 
         if let ControllersList { data, .. } = &msg {
             assert_eq!(2, data.len());
             let c = data.get(&ID::from_u32(12345)).unwrap();
             assert_eq!("Hello", &c.display_name);
+
+            let pairs = msg.data_pairs().unwrap();
+            assert_eq!(48, pairs.len());
+            assert_eq!(("Z_QDGODCNT", 8567.0), pairs[0]);
+            assert_eq!(("Z_QDCYCTIM", 979.0), pairs[1]);
+            assert_eq!(("Z_QDGODCNT", 6031.0), pairs[24]);
+            assert_eq!(("Z_QDHLDTIM", 3928.0), pairs[47]);
+
+            assert_eq!(
+                vec![ID::from_u32(12345), ID::from_u32(22334)],
+                msg.controller_ids_referenced()
+            );
             Ok(())
         } else {
             Err(format!("Expected ControllersList, got {:#?}", msg))
         }
     }
 
+    #[test]
+    fn test_message_controllers_list_explode() -> Result<(), String> {
+        let json = r#"{"$type":"ControllersList","data":{"12345":{"controllerId":12345,"displayName":"Hello","controllerType":"Ai12","version":"1.0.0","model":"JM128-Ai","IP":"192.168.5.1:123","opMode":"Manual","jobMode":"ID11","lastCycleData":{"Z_QDGODCNT":8567,"Z_QDCYCTIM":979},"lastConnectionTime":"2016-03-06T23:11:27.1442177+08:00"},"22334":{"controllerId":22334,"displayName":"World","controllerType":"Ai01","version":"1.0.0","model":"JM128-Ai","IP":"192.168.5.2:234","opMode":"SemiAutomatic","jobMode":"ID12","lastCycleData":{"Z_QDGODCNT":6031,"Z_QDCYCTIM":7526},"lastConnectionTime":"2016-03-06T23:11:27.149218+08:00"}},"sequence":68568}"#;
+
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        let list = msg.explode_controllers_list().unwrap();
+
+        assert_eq!(2, list.len());
+
+        for (m, (id, name, op, job)) in list.iter().zip(&[
+            (12345, "Hello", OpMode::Manual, JobMode::ID11),
+            (22334, "World", OpMode::SemiAutomatic, JobMode::ID12),
+        ]) {
+            m.validate().map_err(|x| x.to_string())?;
+            assert_ne!(68568, m.sequence());
+
+            if let ControllerStatus { controller_id, state, controller: Some(c), .. } = m {
+                assert_eq!(*id, *controller_id);
+                assert_eq!(*name, &c.display_name);
+                assert_eq!(*op, state.op_mode());
+                assert_eq!(*job, state.job_mode());
+            } else {
+                return Err(format!("Expected ControllerStatus, got {:#?}", m));
+            }
+        }
+
+        #[cfg(not(feature = "manual-sequence"))]
+        assert!(list[0].sequence() < list[1].sequence());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_reborrow_in_callback() -> Result<(), String> {
+        let json = String::from(
+            r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","opMode":"Automatic","jobMode":"ID02","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.33},"sequence":1}"#,
+        );
+        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+
+        let mut seen: Vec<&Message> = Vec::new();
+        let mut callback = |m| seen.push(m);
+
+        callback(msg.reborrow());
+
+        {
+            // A message with a shorter lifetime passed to the same callback
+            let json2 = String::from(r#"{"$type":"Alive","sequence":2}"#);
+            let msg2 = Message::parse_from_json_str(&json2).map_err(|x| x.to_string())?;
+            let mut sequences = Vec::new();
+            let mut callback2 = |m: &Message| sequences.push(m.sequence());
+            callback2(msg.reborrow());
+            callback2(msg2.reborrow());
+            assert_eq!(vec![1, 2], sequences);
+        }
+
+        assert_eq!(1, seen.len());
+        assert_eq!(Some(12.33), seen[0].get_value_f32("Z_QDCYCTIM"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_action() -> Result<(), String> {
+        let msg = ControllerAction {
+            controller_id: ID::from_u32(42),
+            action_id: ActionID::new(1014),
+            timestamp: parse_timestamp("2019-02-26T02:03:04+08:00"),
+            options: MessageOptions::default_new(),
+        };
+
+        let (id, action, time) = msg.action().ok_or("Expected ControllerAction")?;
+        assert_eq!(ID::from_u32(42), id);
+        assert_eq!(ActionID::new(1014), action);
+        assert_eq!(parse_timestamp("2019-02-26T02:03:04+08:00"), time);
+
+        assert!(Message::new_alive().action().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_request_job_cards_list_reply() -> Result<(), String> {
+        let json = r#"{"$type":"RequestJobCardsList","controllerId":123,"sequence":1}"#;
+        let request = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        let reply = request.reply_job_cards_list(vec![
+            JobCard::try_new("JOB01", "ABC-123", 0, 8000)?,
+            JobCard::try_new("JOB02", "M002", 2000, 10000)?,
+        ])?;
+        reply.validate().map_err(|x| x.to_string())?;
+
+        let json = reply.to_json_str()?;
+        let reply = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+
+        if let JobCardsList { controller_id, data, .. } = &reply {
+            assert_eq!(123, *controller_id);
+            assert_eq!(vec!["JOB01", "JOB02"], data.keys().map(|k| k.get()).collect::<Vec<_>>());
+        } else {
+            return Err(format!("Expected JobCardsList, got {:#?}", reply));
+        }
+
+        assert!(reply.reply_job_cards_list(vec![]).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_message_cycle_data_from_json() -> Result<(), String> {
         let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","opMode":"Automatic","jobMode":"ID02","controllerId":123,"data":{"Z_QDGODCNT":123,"Z_QDCYCTIM":12.33,"Z_QDINJTIM":3,"Z_QDPLSTIM":4.4,"Z_QDINJENDPOS":30.1,"Z_QDPLSENDPOS":20.3,"Z_QDFLAG":1,"Z_QDPRDCNT":500,"Z_QDCOLTIM":12.12,"Z_QDMLDOPNTIM":2.1,"Z_QDMLDCLSTIM":1.3,"Z_QDVPPOS":12.11,"Z_QDMLDOPNENDPOS":130.1,"Z_QDMAXINJSPD":213.12,"Z_QDMAXPLSRPM":551,"Z_QDNOZTEMP":256,"Z_QDTEMPZ01":251,"Z_QDTEMPZ02":252,"Z_QDTEMPZ03":253,"Z_QDTEMPZ04":254,"Z_QDTEMPZ05":255,"Z_QDTEMPZ06":256,"Z_QDBCKPRS":54,"Z_QDHLDTIM":2.3,"Z_QDCPT01":231,"Z_QDCPT02":232,"Z_QDCPT03":233,"Z_QDCPT04":234,"Z_QDCPT05":235,"Z_QDCPT06":236,"Z_QDCPT07":237,"Z_QDCPT08":238,"Z_QDCPT09":239,"Z_QDCPT10":240,"Z_QDCPT11":241,"Z_QDCPT12":242,"Z_QDCPT13":243,"Z_QDCPT14":244,"Z_QDCPT15":245,"Z_QDCPT16":246,"Z_QDCPT17":247,"Z_QDCPT18":248,"Z_QDCPT19":249,"Z_QDCPT20":250,"Z_QDCPT21":251,"Z_QDCPT22":252,"Z_QDCPT23":253,"Z_QDCPT24":254,"Z_QDCPT25":255,"Z_QDCPT26":256,"Z_QDCPT27":257,"Z_QDCPT28":258,"Z_QDCPT29":259,"Z_QDCPT30":260,"Z_QDCPT31":261,"Z_QDCPT32":262,"Z_QDCPT33":263,"Z_QDCPT34":264,"Z_QDCPT35":265,"Z_QDCPT36":266,"Z_QDCPT37":267,"Z_QDCPT38":268,"Z_QDCPT39":269,"Z_QDCPT40":270},"sequence":1}"#;
 
-        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
 
         if let CycleData { controller_id, data, .. } = &msg {
             assert_eq!(0, msg.priority());
             assert_eq!(123, *controller_id);
             assert_eq!(64, data.len());
             assert!(*data.get(&TextID::new("Z_QDCPT13").unwrap()).unwrap() == R32::new(243.0));
+            assert_eq!(Some(243.0_f32), msg.get_value_f32("Z_QDCPT13"));
+            assert_eq!(None, msg.get_value_f32("Z_QDCPT99"));
+            let keys = msg.data_keys().unwrap();
+            assert_eq!(64, keys.len());
+            assert_eq!("Z_QDGODCNT", keys[0]);
+            #[cfg(feature = "chrono")]
+            assert_eq!(
+                DateTime::parse_from_rfc3339("2016-02-25T17:12:23Z").unwrap().with_timezone(&Utc),
+                msg.timestamp_utc().unwrap()
+            );
             Ok(())
         } else {
             Err(format!("Expected CycleData, got {:#?}", msg))
         }
     }
 
+    #[test]
+    fn test_message_cycle_data_with_too_many_entries_from_json() {
+        let data: Vec<_> = (0..=ParseOptions::DEFAULT_MAX_MAP_ENTRIES)
+            .map(|n| format!(r#""Z_QD{}":{}"#, n, n))
+            .collect();
+        let json = format!(
+            r#"{{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":123,"data":{{{}}},"sequence":1}}"#,
+            data.join(",")
+        );
+
+        let err = Message::parse_from_json_str(&json).unwrap_err().to_string();
+        assert!(err.contains("too many entries"), "{}", err);
+    }
+
+    #[test]
+    fn test_message_cycle_data_with_max_map_entries_from_json() -> Result<(), String> {
+        let json = r#"{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":123,"data":{"Z_QDGAPTIM":100,"Z_QDCYCTIM":200,"Z_QDINJTIM":300},"sequence":1}"#;
+
+        let options = ParseOptions::new().max_map_entries(2);
+        let err =
+            Message::parse_from_json_str_with_options(json, &options).unwrap_err().to_string();
+        assert!(err.contains("a maximum of 2 entries is allowed"), "{}", err);
+
+        let options = ParseOptions::new().max_map_entries(3);
+        let m =
+            Message::parse_from_json_str_with_options(json, &options).map_err(|x| x.to_string())?;
+
+        if let Message::CycleData { data, .. } = m {
+            assert_eq!(3, data.len());
+        } else {
+            panic!("Expected CycleData, got {:#?}", m);
+        }
+
+        // The limit only applies within `parse_from_json_str_with_options`
+        let options = ParseOptions::new().max_map_entries(1_000_000);
+        let data: Vec<_> = (0..=ParseOptions::DEFAULT_MAX_MAP_ENTRIES)
+            .map(|n| format!(r#""Z_QD{}":{}"#, n, n))
+            .collect();
+        let json = format!(
+            r#"{{"$type":"CycleData","timestamp":"2016-02-26T01:12:23+08:00","controllerId":123,"data":{{{}}},"sequence":1}}"#,
+            data.join(",")
+        );
+
+        Message::parse_from_json_str_with_options(&json, &options).map_err(|x| x.to_string())?;
+
+        let err = Message::parse_from_json_str(&json).unwrap_err().to_string();
+        assert!(err.contains("too many entries"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_cycle_data_validate() {
+        let msg = Message::new_cycle_data_simple(
+            ID::from_u32(123),
+            Default::default(),
+            parse_timestamp("2019-02-26T02:03:04+08:00"),
+            OpMode::Automatic,
+            JobMode::ID02,
+        );
+        assert_eq!(Err(Error::EmptyField("data")), msg.validate());
+
+        let mut data = IndexMap::new();
+        data.insert(TextID::new("Z_QDGODCNT").unwrap(), R32::new(123.0));
+
+        let msg = Message::new_cycle_data_simple(
+            ID::from_u32(123),
+            data.clone(),
+            parse_timestamp("2019-02-26T02:03:04+08:00"),
+            OpMode::Offline,
+            JobMode::ID05,
+        );
+        assert_eq!(
+            Err(Error::ConstraintViolated(
+                "Job mode must be Offline when op mode is Offline, not ID05.".into()
+            )),
+            msg.validate()
+        );
+
+        let msg = Message::new_cycle_data_simple(
+            ID::from_u32(123),
+            data,
+            parse_timestamp("2019-02-26T02:03:04+08:00"),
+            OpMode::Automatic,
+            JobMode::ID05,
+        );
+        assert_eq!(Ok(()), msg.validate());
+    }
+
+    #[test]
+    fn test_message_cycle_data_to_json_bounded() {
+        let data: IndexMap<_, _> = (0..1000)
+            .map(|n| (TextID::new(format!("Z_QD{}", n)).unwrap(), R32::new(n as f32)))
+            .collect();
+
+        let msg = Message::new_cycle_data_simple(
+            ID::from_u32(123),
+            data,
+            parse_timestamp("2019-02-26T02:03:04+08:00"),
+            OpMode::Automatic,
+            JobMode::ID02,
+        );
+
+        let len = msg.to_json_str().unwrap().len();
+        assert!(len > 1000);
+
+        match msg.to_json_str_bounded(1000) {
+            Err(Error::ConstraintViolated(err)) => assert!(err.contains("exceeding the maximum")),
+            r => panic!("Expected ConstraintViolated, got {:?}", r),
+        }
+
+        assert_eq!(len, msg.to_json_str_bounded(len).unwrap().len());
+    }
+
     #[test]
     fn test_message_controller_status_without_controller_from_json() -> Result<(), String> {
         let json = r#"{"$type":"ControllerStatus","controllerId":123,"displayName":"Testing","opMode":"Automatic","alarm":{"key":"hello","value":true},"jobMode":"ID05","jobCardId":"XYZ","moldId":"Mold-123","state":{"opMode":"Automatic","jobMode":"ID05","jobCardId":"XYZ","moldId":"Mold-123"},"sequence":1,"priority":50}"#;
 
-        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
 
         if let ControllerStatus { controller_id, display_name, controller, alarm, .. } = &msg {
             assert_eq!(50, msg.priority());
@@ -1161,7 +4084,7 @@ mod test {
     fn test_message_controller_status_with_controller_from_json() -> Result<(), String> {
         let json = r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05","jobCardId":"XYZ","moldId":"Mold-123"},"controller":{"controllerId":123,"displayName":"Testing","controllerType":"Ai02","version":"2.2","model":"JM138Ai","IP":"192.168.1.1:12345","geoLatitude":23.0,"geoLongitude":-121.0,"opMode":"Automatic","jobMode":"ID05","jobCardId":"XYZ","lastCycleData":{"INJ":5,"CLAMP":400},"moldId":"Mold-123"},"sequence":1}"#;
 
-        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
 
         if let ControllerStatus { controller_id, display_name, state, controller, .. } = &msg {
             assert_eq!(0, msg.priority());
@@ -1183,6 +4106,169 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_message_controller_status_with_escapes_from_json_owned() -> Result<(), String> {
+        let msg = {
+            let json = String::from(
+                r#"{"$type":"ControllerStatus","controllerId":123,"displayName":"Hello \"World\"","state":{"opMode":"Automatic","jobMode":"ID05"},"controller":{"controllerId":123,"displayName":"Hello \"World\"","controllerType":"Ai02","version":"2.2","model":"JM138\u0041i","IP":"192.168.1.1:12345","opMode":"Automatic","jobMode":"ID05","lastCycleData":{"INJ\t":5}},"sequence":1}"#,
+            );
+            Message::parse_from_json_str_owned(&json).map_err(|x| x.to_string())?
+        };
+
+        if let ControllerStatus { display_name, controller, .. } = &msg {
+            assert_eq!(Some(r#"Hello "World""#), display_name.as_ref().map(|x| x.get()));
+            let c = controller.as_ref().unwrap();
+            assert_eq!(r#"Hello "World""#, &c.display_name);
+            assert_eq!("JM138Ai", &c.model);
+            assert!(c.last_cycle_data.contains_key("INJ\t"));
+            Ok(())
+        } else {
+            Err(format!("Expected ControllerStatus, got {:#?}", msg))
+        }
+    }
+
+    #[test]
+    fn test_message_controller_status_with_miscased_type_from_json() -> Result<(), String> {
+        let json = r#"{"$type" : "controllerstatus","controllerId":123,"displayName":"Testing","opMode":"Automatic","jobMode":"ID05","state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+
+        assert!(Message::parse_from_json_str(json).is_err());
+
+        let msg = Message::parse_from_json_str_case_insensitive(json).map_err(|x| x.to_string())?;
+
+        if let ControllerStatus { controller_id, display_name, state, .. } = &msg {
+            assert_eq!(123, *controller_id);
+            assert_eq!(Some("Testing"), display_name.as_ref().map(|x| x.get()));
+            assert_eq!(JobMode::ID05, state.job_mode());
+            Ok(())
+        } else {
+            Err(format!("Expected ControllerStatus, got {:#?}", msg))
+        }
+    }
+
+    #[test]
+    fn test_message_redact() -> Result<(), String> {
+        let msg = OperatorInfo {
+            controller_id: ID::from_u32(123),
+            operator_id: Some(ID::from_u32(42)),
+            name: TextName::new_from_str("John").unwrap(),
+            password: TextName::new_from_str("MyPassword").unwrap(),
+            level: 5,
+            options: MessageOptions::default_new(),
+        };
+
+        let serialized = msg.redact().to_json_str().map_err(|x| x.to_string())?;
+        assert!(!serialized.contains("MyPassword"));
+        assert_eq!(
+            r#"{"$type":"OperatorInfo","controllerId":123,"operatorId":42,"name":"John","password":"********","level":5,"sequence":1}"#,
+            serialized
+        );
+
+        let msg = LoginOperator {
+            controller_id: ID::from_u32(123),
+            password: "MyPassword".into(),
+            options: MessageOptions::default_new(),
+        };
+
+        if let LoginOperator { controller_id, password, options } = msg.redact() {
+            assert_eq!(123, controller_id);
+            assert_eq!(Message::REDACTED_PASSWORD, password);
+            assert_eq!(1, options.sequence());
+        } else {
+            panic!();
+        }
+
+        let msg = Message::new_alive();
+        assert_eq!(msg.to_json_str().unwrap(), msg.redact().to_json_str().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_status_with_numeric_audit_from_json() -> Result<(), String> {
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"audit":{"key":"Z_QDGODCNT","value":9007199254740993},"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        if let ControllerStatus { audit: Some(audit), .. } = &msg {
+            assert_eq!("Z_QDGODCNT", audit.key_ref().get());
+            assert_eq!(NumericValue::Int(9_007_199_254_740_993), audit.value());
+        } else {
+            return Err(format!("Expected ControllerStatus with audit, got {:#?}", msg));
+        }
+
+        assert_eq!(json, msg.to_json_str().map_err(|x| x.to_string())?);
+
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"audit":{"key":"Z_QDCYCTIM","value":12.5},"variable":{"key":"Z_QDINJTIM","value":-3.25},"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        if let ControllerStatus { audit: Some(audit), variable: Some(variable), .. } = &msg {
+            assert_eq!(NumericValue::Real(R32::new(12.5)), audit.value());
+            assert_eq!(NumericValue::Real(R32::new(-3.25)), variable.value());
+        } else {
+            return Err(format!(
+                "Expected ControllerStatus with audit and variable, got {:#?}",
+                msg
+            ));
+        }
+
+        assert_eq!(json, msg.to_json_str().map_err(|x| x.to_string())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_status_with_offline_job_mode_from_json() -> Result<(), String> {
+        // From the sample in `cs/doc/messages_reference.md`
+        let json = r#"{"$type":"ControllerStatus","timestamp":"2016-04-01T01:12:23+08:00","controllerId":234,"displayName":"M2","opMode":"Manual","jobMode":"Offline","jobCardId":"XYZ","isDisconnected":false,"alarm":{"key":"DOOROPEN","value":true},"audit":{"key":"PRES","value":50.0},"operatorId":987,"operatorName":"Johnny","moldId":"ABC123","state":{"opMode":"Manual","jobMode":"Offline","operatorId":987,"jobCardId":"XYZ","moldId":"ABC123"},"sequence":123,"priority":10}"#;
+
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some(ID::from_u32(234)), msg.controller_id());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_status_is_disconnected_from_json() -> Result<(), String> {
+        for (text, expected) in
+            &[("true", Some(true)), ("false", Some(false)), ("1", Some(true)), ("0", Some(false))]
+        {
+            let json = format!(
+                r#"{{"$type":"ControllerStatus","controllerId":123,"isDisconnected":{},"state":{{"opMode":"Automatic","jobMode":"ID05"}},"sequence":1}}"#,
+                text
+            );
+            let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+
+            if let ControllerStatus { is_disconnected, .. } = msg {
+                assert_eq!(*expected, is_disconnected);
+            } else {
+                return Err(format!("Expected ControllerStatus, got {:#?}", msg));
+            }
+        }
+
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert!(!msg.is_disconnect_event());
+
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"isDisconnected":2,"state":{"opMode":"Automatic","jobMode":"ID05"},"sequence":1}"#;
+        assert!(Message::parse_from_json_str(json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_status_describe() -> Result<(), String> {
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"opMode":"Manual","jobCardId":null,"state":{"opMode":"Manual","jobMode":"ID05"},"sequence":2}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        assert_eq!(
+            r#"ControllerStatus { controllerId: 123, opMode: "Manual", jobCardId: null, state: {"opMode":"Manual","jobMode":"ID05"}, sequence: 2 }"#,
+            msg.describe()
+        );
+
+        // The derived `Debug` output shows all the `None` fields
+        assert!(format!("{:?}", msg).contains("display_name: None"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_message_controller_status_to_json() -> Result<(), String> {
         let status: Message = ControllerStatus {
@@ -1250,4 +4336,139 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_message_controller_status_with_mismatched_state() -> Result<(), String> {
+        fn check(field: &str, expected: Error<'static>) {
+            let json = format!(
+                r#"{{"$type":"ControllerStatus","controllerId":123,{},"state":{{"opMode":"Automatic","jobMode":"ID02","operatorId":42,"jobCardId":"JC001","moldId":"M001"}},"sequence":1}}"#,
+                field
+            );
+            let msg = Message::parse_from_json_str_owned(&json);
+            assert_eq!(
+                Some(expected.to_string()),
+                msg.err().map(|err| err.to_string()),
+                "{}",
+                field
+            );
+        }
+
+        check(r#""opMode":"Manual""#, Error::InconsistentState("op_mode"));
+        check(r#""jobMode":"ID05""#, Error::InconsistentState("job_mode"));
+        check(r#""operatorId":0"#, Error::InconsistentState("operator_id"));
+        check(r#""operatorId":99"#, Error::InconsistentState("operator_id"));
+        check(r#""jobCardId":null"#, Error::InconsistentState("job_card_id"));
+        check(r#""jobCardId":"JC002""#, Error::InconsistentState("job_card_id"));
+        check(r#""moldId":"M002""#, Error::InconsistentState("mold_id"));
+
+        // Consistent fields are fine
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"opMode":"Automatic","jobMode":"ID02","operatorId":42,"operatorName":"John","jobCardId":"JC001","moldId":"M001","state":{"opMode":"Automatic","jobMode":"ID02","operatorId":42,"jobCardId":"JC001","moldId":"M001"},"sequence":1}"#;
+        Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        // An operator name without a logged-on operator
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"operatorId":0,"operatorName":"John","state":{"opMode":"Automatic","jobMode":"ID02"},"sequence":1}"#;
+        assert_eq!(
+            Error::InconsistentState("operator_name").to_string(),
+            Message::parse_from_json_str(json).unwrap_err().to_string()
+        );
+
+        let json = r#"{"$type":"ControllerStatus","controllerId":123,"operatorId":0,"operatorName":null,"state":{"opMode":"Automatic","jobMode":"ID02"},"sequence":1}"#;
+        Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_controller_status_with_mismatched_controller_id() {
+        let controller = Controller { controller_id: ID::from_u32(999), ..Default::default() };
+
+        let status = ControllerStatus {
+            controller_id: ID::from_u32(12345),
+            display_name: None,
+            is_disconnected: None,
+            op_mode: None,
+            job_mode: None,
+            job_card_id: None,
+            mold_id: None,
+            operator_id: None,
+            operator_name: None,
+            variable: None,
+            audit: None,
+            alarm: None,
+            state: controller.to_state_values(),
+            controller: Some(Box::new(controller.clone())),
+            options: MessageOptions::default_new(),
+        };
+
+        assert_eq!(Err(Error::InconsistentField("controller_id")), status.validate());
+    }
+
+    #[test]
+    fn test_message_mold_data_value_validate_value() {
+        let make = |value: f32| MoldDataValue {
+            controller_id: ID::from_u32(123),
+            field: TextID::new("Z_QDMLDOPNSPD").unwrap(),
+            value: R32::new(value),
+            options: MessageOptions::default_new(),
+        };
+
+        assert_eq!(Ok(()), make(0.0).validate());
+        assert_eq!(Ok(()), make(-123.45).validate());
+
+        // A sub-normal number is a valid `R32`, but not supported by the protocol
+        let err = make(1.0e-40).validate().unwrap_err();
+        assert_eq!(
+            "value [0.0000000000000000000000000000000000000001] is invalid for the field value - sub-normal number is not a supported value",
+            err.to_string()
+        );
+
+        // `R32` only checks for infinities in debug builds, so an infinity introduced by
+        // computation can slip through in release builds
+        #[cfg(not(debug_assertions))]
+        {
+            assert!(make(f32::MAX * 2.0).validate().is_err());
+            assert!(make(f32::NEG_INFINITY).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_message_mold_data_value_setting_name() -> Result<(), String> {
+        let json = r#"{"$type":"MoldDataValue","controllerId":123,"field":"Z_SDINJSPD01","value":42.5,"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some("Z_SDINJSPD01"), msg.setting_name());
+
+        let json =
+            r#"{"$type":"ReadMoldData","controllerId":123,"field":"HelloWorld","sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some("HelloWorld"), msg.setting_name());
+
+        // Unusual setting names are only rejected by `validate_strict`
+        let json = r#"{"$type":"MoldDataValue","controllerId":123,"field":"Z_SD INJSPD01","value":42.5,"sequence":1}"#;
+        let msg = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+        assert_eq!(Some("Z_SD INJSPD01"), msg.setting_name());
+        assert_eq!(
+            Err(Error::InvalidField {
+                field: "field",
+                value: "Z_SD INJSPD01".into(),
+                description: "mold setting name cannot contain white-spaces".into()
+            }),
+            msg.validate_strict()
+        );
+
+        let name = "X".repeat(Message::MAX_SETTING_NAME_LEN + 1);
+        let json = format!(
+            r#"{{"$type":"ReadMoldData","controllerId":123,"field":"{}","sequence":1}}"#,
+            name
+        );
+        let msg = Message::parse_from_json_str(&json).map_err(|x| x.to_string())?;
+        assert_eq!(
+            Err(Error::InvalidField {
+                field: "field",
+                value: name.as_str().into(),
+                description: "mold setting name cannot be longer than 64 characters".into()
+            }),
+            msg.validate_strict()
+        );
+
+        Ok(())
+    }
 }
@@ -1,6 +1,7 @@
-use super::{TextName, ID};
+use super::{Error, Result, TextName, ID};
 use serde::{Deserialize, Serialize};
-use std::convert::TryInto;
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
 
 /// A data structure containing information on a single user on the system.
 ///
@@ -45,6 +46,33 @@ impl<'a> Operator<'a> {
         self.operator_name.as_ref().map(|name| name.as_ref())
     }
 
+    /// Check whether two `Operator`'s refer to the same user.
+    ///
+    /// Only the operator ID's are compared because the ID is the authoritative key of a user.
+    /// Names are ignored, unlike `==` which compares both the ID and the name.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let opr1 = Operator::try_new_with_name(ID::from_u32(12345), "John")?;
+    /// let opr2 = Operator::try_new_with_name(ID::from_u32(12345), "JOHN")?;
+    /// let opr3 = Operator::new(ID::from_u32(12345));
+    /// let opr4 = Operator::try_new_with_name(ID::from_u32(999), "John")?;
+    ///
+    /// assert!(opr1.same_user(&opr2));
+    /// assert_ne!(opr1, opr2);
+    /// assert!(opr1.same_user(&opr3));
+    /// assert_ne!(opr1, opr3);
+    /// assert!(!opr1.same_user(&opr4));
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn same_user(&self, other: &Operator<'_>) -> bool {
+        self.operator_id == other.operator_id
+    }
+
     /// Create an `Operator` with just an ID and no name.
     ///
     /// # Examples
@@ -93,3 +121,51 @@ impl<'a> Operator<'a> {
         })
     }
 }
+
+impl<'a> TryFrom<&'a Value> for Operator<'a> {
+    type Error = Error<'a>;
+
+    /// Parse a JSON value (e.g. an entry in a user list exported from the server) into
+    /// an `Operator`.
+    ///
+    /// An `Operator` has no constraints beyond those of its field types -- the operator ID
+    /// cannot be zero, and the operator name (if any) cannot be empty -- and these are
+    /// already enforced while deserializing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError::JsonError`]`)` if the JSON value cannot be
+    /// deserialized into an `Operator`.
+    ///
+    /// [`OpenProtocolError::JsonError`]: enum.OpenProtocolError.html#variant.JsonError
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// let value = serde_json::json!({ "operatorId": 0, "operatorName": "John" });
+    /// assert!(Operator::try_from(&value).is_err());
+    ///
+    /// let value = serde_json::json!({ "operatorId": 123, "operatorName": "  " });
+    /// assert!(Operator::try_from(&value).is_err());
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let value = serde_json::json!({ "operatorId": 123, "operatorName": "John" });
+    ///
+    /// let user = Operator::try_from(&value).map_err(|err| err.to_string())?;
+    /// assert_eq!(123, user.id());
+    /// assert_eq!(Some("John"), user.name());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(value: &'a Value) -> Result<'a, Self> {
+        Self::deserialize(value).map_err(Error::JsonError)
+    }
+}
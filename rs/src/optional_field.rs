@@ -0,0 +1,187 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A field that can be absent, explicitly set to `null`, or set to a value.
+///
+/// This wraps the `Option<Option<T>>` convention where `None` means the field is absent
+/// (i.e. not relevant), `Some(None)` means the field is `null` (e.g. a user has logged out,
+/// or a value is cleared) and `Some(Some(value))` means the field has a value.
+///
+/// An absent field must be skipped during serialization and defaulted during deserialization,
+/// so a field of this type should be tagged with:
+///
+/// ~~~text
+/// #[serde(default, skip_serializing_if = "OptionalField::is_absent")]
+/// ~~~
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # use serde::{Deserialize, Serialize};
+/// # fn main() -> std::result::Result<(), String> {
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Data {
+///     #[serde(default, skip_serializing_if = "OptionalField::is_absent")]
+///     name: OptionalField<String>,
+/// }
+///
+/// // Absent
+/// let data = Data { name: OptionalField::absent() };
+/// let json = serde_json::to_string(&data).map_err(|err| err.to_string())?;
+/// assert_eq!(r#"{}"#, json);
+/// assert_eq!(data, serde_json::from_str(&json).map_err(|err| err.to_string())?);
+///
+/// // Null
+/// let data = Data { name: OptionalField::null() };
+/// let json = serde_json::to_string(&data).map_err(|err| err.to_string())?;
+/// assert_eq!(r#"{"name":null}"#, json);
+/// assert_eq!(data, serde_json::from_str(&json).map_err(|err| err.to_string())?);
+///
+/// // Value
+/// let data = Data { name: OptionalField::new("John".to_string()) };
+/// let json = serde_json::to_string(&data).map_err(|err| err.to_string())?;
+/// assert_eq!(r#"{"name":"John"}"#, json);
+/// assert_eq!(data, serde_json::from_str(&json).map_err(|err| err.to_string())?);
+/// # Ok(())
+/// # }
+/// ~~~
+///
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub struct OptionalField<T>(Option<Option<T>>);
+
+impl<T> OptionalField<T> {
+    /// Create an absent `OptionalField`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let field: OptionalField<i32> = OptionalField::absent();
+    /// assert!(field.is_absent());
+    /// assert_eq!(None, field.get());
+    /// ~~~
+    pub fn absent() -> Self {
+        OptionalField(None)
+    }
+
+    /// Create an `OptionalField` that is `null`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let field: OptionalField<i32> = OptionalField::null();
+    /// assert!(field.is_null());
+    /// assert_eq!(Some(None), field.get());
+    /// ~~~
+    pub fn null() -> Self {
+        OptionalField(Some(None))
+    }
+
+    /// Create an `OptionalField` with a value.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let field = OptionalField::new(42);
+    /// assert!(!field.is_absent());
+    /// assert!(!field.is_null());
+    /// assert_eq!(Some(Some(&42)), field.get());
+    /// ~~~
+    pub fn new(value: T) -> Self {
+        OptionalField(Some(Some(value)))
+    }
+
+    /// Returns true if the field is absent.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(OptionalField::<i32>::absent().is_absent());
+    /// assert!(!OptionalField::<i32>::null().is_absent());
+    /// assert!(!OptionalField::new(42).is_absent());
+    /// ~~~
+    pub fn is_absent(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns true if the field is `null`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(!OptionalField::<i32>::absent().is_null());
+    /// assert!(OptionalField::<i32>::null().is_null());
+    /// assert!(!OptionalField::new(42).is_null());
+    /// ~~~
+    pub fn is_null(&self) -> bool {
+        matches!(self.0, Some(None))
+    }
+
+    /// Get a reference to the value of the field as an `Option<Option<&T>>`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(None, OptionalField::<i32>::absent().get());
+    /// assert_eq!(Some(None), OptionalField::<i32>::null().get());
+    /// assert_eq!(Some(Some(&42)), OptionalField::new(42).get());
+    /// ~~~
+    pub fn get(&self) -> Option<Option<&T>> {
+        self.0.as_ref().map(Option::as_ref)
+    }
+
+    /// Convert the field into an `Option<Option<T>>`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(None, OptionalField::<i32>::absent().into_inner());
+    /// assert_eq!(Some(None), OptionalField::<i32>::null().into_inner());
+    /// assert_eq!(Some(Some(42)), OptionalField::new(42).into_inner());
+    /// ~~~
+    pub fn into_inner(self) -> Option<Option<T>> {
+        self.0
+    }
+}
+
+impl<T> Default for OptionalField<T> {
+    /// Default value for `OptionalField` is absent.
+    fn default() -> Self {
+        Self::absent()
+    }
+}
+
+impl<T> From<Option<Option<T>>> for OptionalField<T> {
+    fn from(value: Option<Option<T>>) -> Self {
+        OptionalField(value)
+    }
+}
+
+impl<T> From<OptionalField<T>> for Option<Option<T>> {
+    fn from(value: OptionalField<T>) -> Self {
+        value.0
+    }
+}
+
+impl<T: Serialize> Serialize for OptionalField<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // An absent field should have been skipped, so serialize it as null also
+        match &self.0 {
+            Some(Some(value)) => value.serialize(serializer),
+            _ => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for OptionalField<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // A present field is either null or a value; an absent field is handled by `default`
+        Option::<T>::deserialize(deserializer).map(|value| OptionalField(Some(value)))
+    }
+}
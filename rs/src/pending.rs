@@ -0,0 +1,146 @@
+use super::Message;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A tracker of pending requests, for measuring the latency of request/response pairs.
+///
+/// Requests and responses are correlated by the message ID (see [`Message::id`]).
+/// Messages without an ID are not tracked.
+///
+/// [`Message::id`]: enum.Message.html#method.id
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # fn main() -> std::result::Result<(), String> {
+/// let mut pending = PendingRequests::new();
+///
+/// let request = Message::RequestMoldData {
+///     controller_id: ID::from_u32(123),
+///     options: Default::default(),
+/// }.with_id("req-42")?;
+///
+/// assert!(pending.record(&request));
+/// assert_eq!(1, pending.len());
+///
+/// // ... later, when the response arrives
+/// let response = Message::new_alive().with_id("req-42")?;
+/// let elapsed = pending.complete(&response).unwrap();
+/// assert!(elapsed.as_secs() < 60);
+/// assert!(pending.is_empty());
+///
+/// // Not pending anymore
+/// assert_eq!(None, pending.complete(&response));
+/// # Ok(())
+/// # }
+/// ~~~
+#[derive(Debug, Clone, Default)]
+pub struct PendingRequests {
+    requests: HashMap<String, Instant>,
+}
+
+impl PendingRequests {
+    /// Create a new, empty `PendingRequests`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Get the number of pending requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns true if there are no pending requests.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Returns true if a request with a message ID is pending.
+    pub fn is_pending(&self, id: &str) -> bool {
+        self.requests.contains_key(id)
+    }
+
+    /// Record the sending of a request message at the current time.
+    ///
+    /// If a request with the same message ID is already pending, its time is replaced.
+    ///
+    /// Returns `false` (and nothing is recorded) if the message has no ID.
+    pub fn record(&mut self, request: &Message<'_>) -> bool {
+        match request.id() {
+            Some(id) => {
+                self.requests.insert(id.to_string(), Instant::now());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Match a response message to a pending request with the same message ID,
+    /// returning the time elapsed since the request was recorded.
+    ///
+    /// The request is no longer pending afterwards.
+    ///
+    /// Returns `None` if the message has no ID, or no request with the same ID is pending.
+    pub fn complete(&mut self, response: &Message<'_>) -> Option<Duration> {
+        let id = response.id()?;
+        self.requests.remove(id).map(|sent| sent.elapsed())
+    }
+
+    /// Remove all pending requests recorded earlier than `timeout` ago,
+    /// returning their message ID's.
+    pub fn expire(&mut self, timeout: Duration) -> Vec<String> {
+        let now = Instant::now();
+
+        let expired: Vec<_> = self
+            .requests
+            .iter()
+            .filter(|(_, &sent)| now.duration_since(sent) >= timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        expired.iter().for_each(|id| {
+            self.requests.remove(id);
+        });
+
+        expired
+    }
+
+    /// Forget all pending requests.
+    pub fn clear(&mut self) {
+        self.requests.clear();
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MessageOptions, ID};
+    use std::result::Result;
+
+    #[test]
+    fn test_pending_requests_job_cards_list() -> Result<(), String> {
+        let mut pending = PendingRequests::new();
+
+        let request = Message::RequestJobCardsList {
+            controller_id: ID::from_u32(123),
+            options: MessageOptions::new(),
+        }
+        .with_id("req-1")?;
+
+        assert!(pending.record(&request));
+        assert!(!pending.record(&Message::new_alive()));
+        assert!(pending.is_pending("req-1"));
+
+        let json = r#"{"$type":"JobCardsList","controllerId":123,"data":{"JOB01":{"jobCardId":"JOB01","moldId":"MOULD-01","progress":0,"total":1000}},"id":"req-1","sequence":1}"#;
+        let response = Message::parse_from_json_str(json).map_err(|x| x.to_string())?;
+
+        let elapsed = pending.complete(&response).unwrap();
+        assert!(elapsed >= std::time::Duration::from_secs(0));
+        assert!(pending.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+//! A prelude for convenient access to commonly-used types and traits.
+//!
+//! # Examples
+//!
+//! ~~~
+//! use ichen_openprotocol::prelude::*;
+//!
+//! # fn main() -> std::result::Result<(), String> {
+//! let msg = Message::try_new_join_with_org("MyPassword", Filters::Status + Filters::Cycle, "MyCompany")?;
+//! assert_eq!(Some("MyCompany"), msg.org_id());
+//!
+//! let id: ID = 42.try_into()?;
+//! let addr = Address::try_from("1.2.3.4:5")?;
+//!
+//! let c = Controller {
+//!     controller_id: id,
+//!     display_name: "Hello".try_into()?,
+//!     address: addr,
+//!     op_mode: OpMode::Automatic,
+//!     job_mode: JobMode::ID02,
+//!     ..Default::default()
+//! };
+//!
+//! let msg = Message::new_controllers_list(vec![c])?;
+//! assert_eq!(Ok(()), msg.validate());
+//! # Ok(())
+//! # }
+//! ~~~
+
+pub use super::{
+    ActionID, Address, Controller, Filters, GeoLocation, JobCard, JobMode, KeyValuePair, Language,
+    Message, MessageKind, MessageOptions, OpMode, OpenProtocolError, Operator, StateValues, TextID,
+    TextName, ID, R32,
+};
+pub use std::convert::{TryFrom, TryInto};
@@ -0,0 +1,197 @@
+use super::{Filters, Message};
+
+/// State of a client's connection session with the iChen Server.
+///
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum SessionState {
+    /// Not connected to the server.
+    Disconnected,
+    /// A `JOIN` message has been sent, waiting for the server's response.
+    Joining,
+    /// Successfully joined the server with the allowed access level.
+    Joined {
+        /// The allowed access level for this client.
+        level: u32,
+    },
+    /// Failed to join the server.
+    Failed {
+        /// Result code returned by the server (< 100).
+        result: u32,
+    },
+}
+
+impl Default for SessionState {
+    /// Default value for `SessionState` is `Disconnected`.
+    fn default() -> Self {
+        SessionState::Disconnected
+    }
+}
+
+/// A state machine that handles the Open Protocol handshake for a client.
+///
+/// The protocol flow is:
+///
+/// 1. The client sends a [`Join`] message.
+/// 2. The server replies with a [`JoinResponse`] message.  A result code < 100 indicates failure.
+/// 3. When the `JOIN` is successful, the client sends a [`RequestControllersList`] message.
+/// 4. The client replies to each [`Alive`] message from the server with an `Alive` message.
+///
+/// [`Join`]: enum.Message.html#variant.Join
+/// [`JoinResponse`]: enum.Message.html#variant.JoinResponse
+/// [`RequestControllersList`]: enum.Message.html#variant.RequestControllersList
+/// [`Alive`]: enum.Message.html#variant.Alive
+///
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct Session {
+    state: SessionState,
+}
+
+impl Session {
+    /// Create a new `Session` in the `Disconnected` state.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let session = Session::new();
+    /// assert_eq!(SessionState::Disconnected, session.state());
+    /// ~~~
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Get the current state of the `Session`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut session = Session::new();
+    /// let _ = session.join("MyPassword", Filters::All);
+    /// assert_eq!(SessionState::Joining, session.state());
+    /// ~~~
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Create a `JOIN` message to send to the server, and move the `Session` into
+    /// the `Joining` state.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut session = Session::new();
+    /// let msg = session.join("MyPassword", Filters::Status + Filters::Cycle);
+    ///
+    /// if let Message::Join { password, filter, .. } = msg {
+    ///     assert_eq!("MyPassword", password);
+    ///     assert_eq!(Filters::Status + Filters::Cycle, filter);
+    /// } else {
+    ///     panic!();
+    /// }
+    ///
+    /// assert_eq!(SessionState::Joining, session.state());
+    /// ~~~
+    pub fn join<'a>(&mut self, password: &'a str, filter: Filters) -> Message<'a> {
+        self.state = SessionState::Joining;
+        Message::new_join(password, filter)
+    }
+
+    /// Move the `Session` into the `Disconnected` state (e.g. when the connection is closed).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let mut session = Session::new();
+    /// let _ = session.join("MyPassword", Filters::All);
+    /// session.disconnect();
+    /// assert_eq!(SessionState::Disconnected, session.state());
+    /// ~~~
+    pub fn disconnect(&mut self) {
+        self.state = SessionState::Disconnected;
+    }
+
+    /// Process an incoming message from the server, advance the state of the `Session`,
+    /// and return the messages (if any) to send back to the server.
+    ///
+    /// * An `Alive` message is always answered by an `Alive` message.
+    /// * A `JoinResponse` message received in the `Joining` state moves the `Session` into
+    ///   either the `Joined` or `Failed` state.  On success, a `RequestControllersList`
+    ///   message is returned.
+    /// * All other messages are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut session = Session::new();
+    /// let _ = session.join("MyPassword", Filters::All);
+    ///
+    /// // Successful join
+    /// let json = r#"{"$type":"JoinResponse","result":100,"level":5,"sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// let replies = session.on_message(&msg);
+    ///
+    /// assert_eq!(SessionState::Joined { level: 5 }, session.state());
+    /// assert_eq!(1, replies.len());
+    /// match &replies[0] {
+    ///     Message::RequestControllersList { controller_id: None, .. } => (),
+    ///     m => panic!("Expected RequestControllersList, got {:?}", m),
+    /// }
+    ///
+    /// // Keep alive
+    /// let replies = session.on_message(&Message::new_alive());
+    /// assert_eq!(1, replies.len());
+    /// match &replies[0] {
+    ///     Message::Alive { .. } => (),
+    ///     m => panic!("Expected Alive, got {:?}", m),
+    /// }
+    /// assert_eq!(SessionState::Joined { level: 5 }, session.state());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let mut session = Session::new();
+    /// let _ = session.join("WrongPassword", Filters::All);
+    ///
+    /// // Failed join
+    /// let json = r#"{"$type":"JoinResponse","result":2,"message":"Invalid password","sequence":1}"#;
+    /// let msg = Message::parse_from_json_str(json).map_err(|err| err.to_string())?;
+    /// let replies = session.on_message(&msg);
+    ///
+    /// assert_eq!(SessionState::Failed { result: 2 }, session.state());
+    /// assert!(replies.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    pub fn on_message(&mut self, message: &Message<'_>) -> Vec<Message<'static>> {
+        match message {
+            Message::Alive { .. } => vec![Message::new_alive()],
+
+            // Result < 100 indicates failure
+            Message::JoinResponse { result, .. }
+                if self.state == SessionState::Joining && *result < 100 =>
+            {
+                self.state = SessionState::Failed { result: *result };
+                vec![]
+            }
+
+            // When the `JOIN` is successful, request the list of controllers
+            Message::JoinResponse { level, .. } if self.state == SessionState::Joining => {
+                self.state = SessionState::Joined { level: level.unwrap_or(0) };
+                vec![Message::RequestControllersList {
+                    controller_id: None,
+                    options: Default::default(),
+                }]
+            }
+
+            _ => vec![],
+        }
+    }
+}
@@ -1,6 +1,7 @@
-use super::{JobMode, OpMode, TextName, ID};
+use super::{validate_offline_consistency, Error, JobMode, OpMode, Result, TextName, ID};
 use serde::{Deserialize, Serialize};
-use std::convert::TryInto;
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
 
 /// A data structure containing a snapshot of the current known states of the controller.
 ///
@@ -181,8 +182,11 @@ impl<'a> StateValues<'a> {
     ///
     /// # Errors
     ///
-    /// Returns `Err(String)` if `job_card_id` or `mold_id` is set to an empty string
-    /// or is all whitespace.
+    /// Returns `Err(String)` if:
+    /// * `job_card_id` or `mold_id` is set to an empty string or is all whitespace,
+    /// * `op` is `Offline` but `job` is on-line (see [`validate_offline_consistency`]).
+    ///
+    /// [`validate_offline_consistency`]: fn.validate_offline_consistency.html
     ///
     /// ## Error Examples
     ///
@@ -196,6 +200,15 @@ impl<'a> StateValues<'a> {
     ///     None
     /// );
     /// assert_eq!(Err("invalid value: a non-empty, non-whitespace string required for job card ID".into()), sv);
+    ///
+    /// let sv = StateValues::try_new_with_all(
+    ///     OpMode::Offline,
+    ///     JobMode::ID05,     // <-- Notice job mode is not Offline
+    ///     None,
+    ///     None,
+    ///     None
+    /// );
+    /// assert_eq!(Err("Job mode must be Offline when op mode is Offline, not ID05.".into()), sv);
     /// ~~~
     ///
     /// # Examples
@@ -230,6 +243,8 @@ impl<'a> StateValues<'a> {
         job_card_id: Option<&'a str>,
         mold_id: Option<&'a str>,
     ) -> std::result::Result<Self, String> {
+        validate_offline_consistency(op, job)?;
+
         let job_card_id = if let Some(jc) = job_card_id {
             Some(jc.try_into().map(Box::new).map_err(|e| format!("{} for job card ID", e))?)
         } else {
@@ -244,6 +259,22 @@ impl<'a> StateValues<'a> {
 
         Ok(Self { operator_id, job_card_id, mold_id, ..Self::new(op, job) })
     }
+
+    /// Create a new `StateValues` from already-validated field values.
+    pub(crate) fn from_parts(
+        op: OpMode,
+        job: JobMode,
+        operator_id: Option<ID>,
+        job_card_id: Option<TextName<'a>>,
+        mold_id: Option<TextName<'a>>,
+    ) -> Self {
+        Self {
+            operator_id,
+            job_card_id: job_card_id.map(Box::new),
+            mold_id: mold_id.map(Box::new),
+            ..Self::new(op, job)
+        }
+    }
 }
 
 impl Default for StateValues<'_> {
@@ -259,3 +290,66 @@ impl Default for StateValues<'_> {
         }
     }
 }
+
+impl<'a> TryFrom<&'a Value> for StateValues<'a> {
+    type Error = Error<'a>;
+
+    /// Parse a JSON value (e.g. the `state` object of a message that was kept as a
+    /// `serde_json::Value`) into a `StateValues`.
+    ///
+    /// As with [`try_new_with_all`], the op mode and job mode must be consistent
+    /// (see [`validate_offline_consistency`]).
+    ///
+    /// [`try_new_with_all`]: struct.StateValues.html#method.try_new_with_all
+    /// [`validate_offline_consistency`]: fn.validate_offline_consistency.html
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(`[`OpenProtocolError::JsonError`]`)` if the JSON value cannot be
+    /// deserialized into a `StateValues` (e.g. the mold ID is empty).
+    ///
+    /// Returns `Err(`[`OpenProtocolError::ConstraintViolated`]`)` if the op mode is `Offline`
+    /// but the job mode is on-line.
+    ///
+    /// [`OpenProtocolError::JsonError`]: enum.OpenProtocolError.html#variant.JsonError
+    /// [`OpenProtocolError::ConstraintViolated`]: enum.OpenProtocolError.html#variant.ConstraintViolated
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// let value = serde_json::json!({ "opMode": "Offline", "jobMode": "ID02" });
+    ///
+    /// assert_eq!(
+    ///     Err(OpenProtocolError::ConstraintViolated(
+    ///         "Job mode must be Offline when op mode is Offline, not ID02.".into()
+    ///     )),
+    ///     StateValues::try_from(&value)
+    /// );
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// let value = serde_json::json!({ "opMode": "Automatic", "jobMode": "ID02", "moldId": "M001" });
+    ///
+    /// let state = StateValues::try_from(&value).map_err(|err| err.to_string())?;
+    /// assert_eq!(OpMode::Automatic, state.op_mode());
+    /// assert_eq!(JobMode::ID02, state.job_mode());
+    /// assert_eq!(Some("M001"), state.mold_id());
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(value: &'a Value) -> Result<'a, Self> {
+        let state = Self::deserialize(value).map_err(Error::JsonError)?;
+
+        validate_offline_consistency(state.op_mode, state.job_mode)
+            .map_err(|err| Error::ConstraintViolated(err.into()))?;
+
+        Ok(state)
+    }
+}
@@ -1,4 +1,9 @@
+use super::utils::deserialize_cow_str;
 use derive_more::*;
+#[cfg(not(feature = "chrono"))]
+use lazy_static::*;
+#[cfg(not(feature = "chrono"))]
+use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::{Borrow, Cow};
 use std::cmp::{Ordering, PartialEq, PartialOrd};
@@ -6,13 +11,46 @@ use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
 
-/// A text string ID that cannot be empty or all-whitespace, and must be all-ASCII.
+#[cfg(not(feature = "chrono"))]
+lazy_static! {
+    static ref RFC3339_REGEX: Regex =
+        Regex::new(r#"^\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$"#)
+            .unwrap();
+}
+
+/// A `Cow<str>` for a text string ID that cannot be empty or all-whitespace, and must be all-ASCII.
 ///
 /// This type is usually used for specifying a unique ID.
 ///
 /// It `Deref`s to `&str`.
 ///
-pub type TextID<'a> = ConstrainedText<&'a str, NonEmptyAllASCII>;
+pub type TextID<'a> = ConstrainedText<Cow<'a, str>, NonEmptyAllASCII>;
+
+/// A `Cow<str>` for a text string ID that cannot be empty, must be all-ASCII, and cannot
+/// have leading or trailing whitespace.
+///
+/// This type is a stricter version of [`TextID`] for ID's that must be exact tokens.
+///
+/// It `Deref`s to `&str`.
+///
+/// [`TextID`]: type.TextID.html
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// assert_eq!("ABC", StrictID::new("ABC").unwrap().get());
+/// assert_eq!(None, StrictID::new(" ABC"));
+/// assert_eq!(None, StrictID::new("ABC "));
+/// assert_eq!(None, StrictID::new("   "));
+/// assert_eq!(None, StrictID::new(""));
+/// assert_eq!(None, StrictID::new("你好吗？"));
+///
+/// // A `TextID` accepts leading/trailing whitespace
+/// assert_eq!(" ABC", TextID::new(" ABC").unwrap().get());
+/// ~~~
+///
+pub type StrictID<'a> = ConstrainedText<Cow<'a, str>, NonEmptyTrimmedAllASCII>;
 
 /// A `Cow<str>` for a name that cannot be empty or all-whitespace.
 ///
@@ -61,18 +99,58 @@ impl TextConstraint for NonEmptyAllASCII {
         Self
     }
     fn check(text: &str) -> bool {
-        !text.trim().is_empty() && text.chars().all(|c| char::is_ascii(&c))
+        !text.trim().is_empty() && text.is_ascii()
     }
     fn required() -> &'static str {
         "a non-empty, non-whitespace, all-ASCII string"
     }
 }
 
+/// A text constraint that rejects empty strings and strings with leading or trailing whitespaces.
+/// Only ASCII characters can be in the text string.
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct NonEmptyTrimmedAllASCII;
+
+impl TextConstraint for NonEmptyTrimmedAllASCII {
+    fn new() -> Self {
+        Self
+    }
+    fn check(text: &str) -> bool {
+        !text.is_empty() && text.trim() == text && text.is_ascii()
+    }
+    fn required() -> &'static str {
+        "a non-empty, all-ASCII string without leading or trailing whitespaces"
+    }
+}
+
+/// A text constraint that only accepts RFC3339 timestamps with a time zone offset
+/// (e.g. `2019-02-26T02:03:04+08:00`, `2019-02-25T18:03:04.123Z`).
+///
+/// Only the format is checked; the date and time values are not range-checked.
+///
+#[cfg(not(feature = "chrono"))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rfc3339;
+
+#[cfg(not(feature = "chrono"))]
+impl TextConstraint for Rfc3339 {
+    fn new() -> Self {
+        Self
+    }
+    fn check(text: &str) -> bool {
+        RFC3339_REGEX.is_match(text)
+    }
+    fn required() -> &'static str {
+        "an RFC3339 timestamp"
+    }
+}
+
 /// A data structure that wraps a text string (or anything that dereferences into a text string)
 /// while guaranteeing that the specified text constraint is upheld.
 ///
 #[derive(Display, Clone, Ord, Eq, Hash)]
-#[display(fmt = "_0")]
+#[display(fmt = "{}", "_0.as_ref()")]
 pub struct ConstrainedText<T: AsRef<str>, C: TextConstraint>(T, C);
 
 impl<T: AsRef<str>, C: TextConstraint> Debug for ConstrainedText<T, C> {
@@ -105,7 +183,9 @@ impl<T: AsRef<str>, C: TextConstraint> ConstrainedText<T, C> {
     /// let id = TextName::new_from_str("你好吗？").unwrap();
     /// assert_eq!("你好吗？", &id);
     /// ~~~
-    pub fn new(text: T) -> Option<Self> {
+    pub fn new<V: Into<T>>(text: V) -> Option<Self> {
+        let text = text.into();
+
         if !C::check(text.as_ref()) {
             None
         } else {
@@ -125,6 +205,53 @@ impl<T: AsRef<str>, C: TextConstraint> ConstrainedText<T, C> {
     pub fn get(&self) -> &str {
         self.0.as_ref()
     }
+
+    /// Returns true if the text starts with `prefix` (case-sensitive).
+    ///
+    /// This is the same as `starts_with` (via `Deref` to `str`), but documents the intent
+    /// when filtering by prefix.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let name = TextName::new_from_str("MOULD_003").unwrap();
+    /// assert!(name.matches_prefix("MOULD_"));
+    /// assert!(name.matches_prefix(""));
+    /// assert!(!name.matches_prefix("mould_"));
+    /// ~~~
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        self.get().starts_with(prefix)
+    }
+}
+
+impl<'a, C: TextConstraint> ConstrainedText<Cow<'a, str>, C> {
+    /// Convert a `ConstrainedText` into one that owns its text string.
+    ///
+    /// This clones the text string if it is borrowed, so the result is no longer tied to
+    /// the lifetime of the source (e.g. the JSON text that a message was parsed from).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// let id: TextID<'static> = {
+    ///     let buffer = String::from("hello");
+    ///     TextID::new(buffer.as_str()).unwrap().into_owned()
+    /// };
+    ///
+    /// // `buffer` has been dropped
+    /// assert_eq!("hello", &id);
+    /// ~~~
+    pub fn into_owned(self) -> ConstrainedText<Cow<'static, str>, C> {
+        ConstrainedText(Cow::Owned(self.0.into_owned()), self.1)
+    }
+
+    /// Create a `ConstrainedText` that borrows its text string from this one.
+    #[cfg(not(feature = "chrono"))]
+    pub(crate) fn as_borrowed(&self) -> ConstrainedText<Cow<'_, str>, C> {
+        ConstrainedText(Cow::Borrowed(self.get()), C::new())
+    }
 }
 
 impl<'a, T, C> TryFrom<&'a str> for ConstrainedText<T, C>
@@ -135,7 +262,7 @@ where
     type Error = String;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        Self::new(value.into()).ok_or_else(|| format!("invalid value: {} required", C::required()))
+        Self::new(value).ok_or_else(|| format!("invalid value: {} required", C::required()))
     }
 }
 
@@ -203,21 +330,26 @@ impl<T: AsRef<str>, C: TextConstraint> Serialize for ConstrainedText<T, C> {
 
 impl<'a, 'de: 'a, T, C> Deserialize<'de> for ConstrainedText<T, C>
 where
-    T: AsRef<str> + From<&'a str>,
+    T: AsRef<str> + From<Cow<'a, str>>,
     C: TextConstraint,
 {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let string_val: &str = Deserialize::deserialize(deserializer)?;
-        let value = string_val.into();
+        let value: T = deserialize_cow_str(deserializer)?.into();
+
+        if !C::check(value.as_ref()) {
+            return Err(serde::de::Error::custom(format!(
+                "expected {}, got [{}]",
+                C::required(),
+                value.as_ref()
+            )));
+        }
 
-        ConstrainedText::new(value).ok_or_else(|| {
-            serde::de::Error::custom(format!("expected {}, got [{}]", C::required(), string_val))
-        })
+        Ok(ConstrainedText(value, C::new()))
     }
 }
 
 impl<'a> TextName<'a> {
     pub fn new_from_str<T: Into<Cow<'a, str>>>(text: T) -> Option<Self> {
-        Self::new(text.into())
+        Self::new(text)
     }
 }
@@ -1,3 +1,4 @@
+use super::R32;
 use derive_more::*;
 use serde::{Deserialize, Serialize};
 use std::cmp::{Ordering, PartialEq, PartialOrd};
@@ -64,6 +65,36 @@ impl Language {
     }
 }
 
+impl AsRef<str> for Language {
+    /// Get the language code (e.g. `en`, `zh-tw`) of a `Language`.
+    ///
+    /// `Unknown` returns `und` (undetermined).
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!("en", Language::EN.as_ref());
+    /// assert_eq!("zh-tw", Language::B5.as_ref());
+    /// assert_eq!("zh-cn", Language::GB.as_ref());
+    /// assert_eq!("und", Language::Unknown.as_ref());
+    /// ~~~
+    fn as_ref(&self) -> &str {
+        match self {
+            Language::Unknown => "und",
+            Language::EN => "en",
+            Language::B5 => "zh-tw",
+            Language::GB => "zh-cn",
+            Language::FR => "fr",
+            Language::DE => "de",
+            Language::IT => "it",
+            Language::ES => "es",
+            Language::PT => "pt",
+            Language::JA => "ja",
+        }
+    }
+}
+
 impl Default for Language {
     /// Default value for `Language`.
     fn default() -> Self {
@@ -144,10 +175,7 @@ impl OpMode {
     /// ~~~
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn is_online(&self) -> bool {
-        match self {
-            OpMode::Unknown | OpMode::Offline => false,
-            _ => true,
-        }
+        !matches!(self, OpMode::Unknown | OpMode::Offline)
     }
 
     /// A machine is producing if it is in either `Automatic` or `Semi-Automatic` mode.
@@ -163,13 +191,94 @@ impl OpMode {
     /// ~~~
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn is_producing(&self) -> bool {
+        matches!(self, OpMode::SemiAutomatic | OpMode::Automatic)
+    }
+
+    /// Returns true if `Others`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(OpMode::Others.is_other());
+    /// assert!(!OpMode::Manual.is_other());
+    /// assert!(!OpMode::Unknown.is_other());
+    /// ~~~
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn is_other(&self) -> bool {
+        *self == OpMode::Others
+    }
+
+    /// Get the category of the operating mode, useful for grouping machines on a dashboard.
+    ///
+    /// * `Automatic` and `Semi-Automatic` are `"producing"`.
+    /// * `Manual` is `"manual"`.
+    /// * `Offline` is `"offline"`.
+    /// * `Unknown` is `"unknown"`.
+    /// * `Others` is `"other"`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!("unknown", OpMode::Unknown.category());
+    /// assert_eq!("manual", OpMode::Manual.category());
+    /// assert_eq!("producing", OpMode::SemiAutomatic.category());
+    /// assert_eq!("producing", OpMode::Automatic.category());
+    /// assert_eq!("other", OpMode::Others.category());
+    /// assert_eq!("offline", OpMode::Offline.category());
+    /// ~~~
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn category(&self) -> &'static str {
         match self {
-            OpMode::SemiAutomatic | OpMode::Automatic => true,
-            _ => false,
+            OpMode::Unknown => "unknown",
+            OpMode::Manual => "manual",
+            OpMode::SemiAutomatic | OpMode::Automatic => "producing",
+            OpMode::Others => "other",
+            OpMode::Offline => "offline",
         }
     }
 }
 
+impl OpMode {
+    /// Get the variant name of an `OpMode`, as used in JSON (e.g. `SemiAutomatic`).
+    fn name(self) -> &'static str {
+        match self {
+            OpMode::Unknown => "Unknown",
+            OpMode::Manual => "Manual",
+            OpMode::SemiAutomatic => "SemiAutomatic",
+            OpMode::Automatic => "Automatic",
+            OpMode::Others => "Others",
+            OpMode::Offline => "Offline",
+        }
+    }
+}
+
+impl PartialEq<&str> for OpMode {
+    /// Compare an `OpMode` with its variant name, as used in JSON (e.g. `SemiAutomatic`).
+    ///
+    /// The comparison is case-sensitive.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(OpMode::Automatic == "Automatic");
+    /// assert!(OpMode::SemiAutomatic == "SemiAutomatic");
+    /// assert!(OpMode::SemiAutomatic != "Semi-Automatic");
+    /// assert!(OpMode::Automatic != "automatic");
+    /// ~~~
+    fn eq(&self, other: &&str) -> bool {
+        self.name() == *other
+    }
+}
+
+impl PartialEq<OpMode> for &str {
+    fn eq(&self, other: &OpMode) -> bool {
+        *self == other.name()
+    }
+}
+
 impl Default for OpMode {
     /// Default value for `OpMode`.
     fn default() -> Self {
@@ -258,13 +367,150 @@ impl JobMode {
     /// ~~~
     #[allow(clippy::trivially_copy_pass_by_ref)]
     pub fn is_online(&self) -> bool {
+        !matches!(self, JobMode::Unknown | JobMode::Offline)
+    }
+
+    /// Get the `JobMode` (`ID01` - `ID15`) for a job mode number (1-15).
+    ///
+    /// Returns `None` if `num` is not between 1 and 15.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(Some(JobMode::ID01), JobMode::from_number(1));
+    /// assert_eq!(Some(JobMode::ID05), JobMode::from_number(5));
+    /// assert_eq!(Some(JobMode::ID15), JobMode::from_number(15));
+    /// assert_eq!(None, JobMode::from_number(0));
+    /// assert_eq!(None, JobMode::from_number(16));
+    /// ~~~
+    pub fn from_number(num: u8) -> Option<Self> {
+        Some(match num {
+            1 => JobMode::ID01,
+            2 => JobMode::ID02,
+            3 => JobMode::ID03,
+            4 => JobMode::ID04,
+            5 => JobMode::ID05,
+            6 => JobMode::ID06,
+            7 => JobMode::ID07,
+            8 => JobMode::ID08,
+            9 => JobMode::ID09,
+            10 => JobMode::ID10,
+            11 => JobMode::ID11,
+            12 => JobMode::ID12,
+            13 => JobMode::ID13,
+            14 => JobMode::ID14,
+            15 => JobMode::ID15,
+            _ => return None,
+        })
+    }
+}
+
+impl TryFrom<&str> for JobMode {
+    type Error = String;
+
+    /// Parse a `JobMode` from its name (e.g. `ID05`, `Unknown`, `Offline`) or
+    /// from a bare job mode number made up of ASCII digits only (e.g. `5` for `ID05`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the text is not a valid job mode.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// assert_eq!(Err("invalid job mode: [0]".into()), JobMode::try_from("0"));
+    /// assert_eq!(Err("invalid job mode: [16]".into()), JobMode::try_from("16"));
+    /// assert_eq!(Err("invalid job mode: [ID16]".into()), JobMode::try_from("ID16"));
+    /// assert_eq!(Err("invalid job mode: [hello]".into()), JobMode::try_from("hello"));
+    /// assert_eq!(Err("invalid job mode: [+5]".into()), JobMode::try_from("+5"));
+    /// assert_eq!(Err("invalid job mode: [ID+5]".into()), JobMode::try_from("ID+5"));
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// # fn main() -> std::result::Result<(), String> {
+    /// assert_eq!(JobMode::ID05, JobMode::try_from("5")?);
+    /// assert_eq!(JobMode::ID05, JobMode::try_from("05")?);
+    /// assert_eq!(JobMode::ID05, JobMode::try_from("ID05")?);
+    /// assert_eq!(JobMode::ID15, JobMode::try_from("15")?);
+    /// assert_eq!(JobMode::Offline, JobMode::try_from("Offline")?);
+    /// assert_eq!(JobMode::Unknown, JobMode::try_from("Unknown")?);
+    /// # Ok(())
+    /// # }
+    /// ~~~
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        let num = match text {
+            "Unknown" => return Ok(JobMode::Unknown),
+            "Offline" => return Ok(JobMode::Offline),
+            t if t.starts_with("ID") && t.len() == 4 => &t[2..],
+            t => t,
+        };
+
+        // Only plain ASCII digits are accepted (`u8::from_str` also takes a leading `+`)
+        Some(num)
+            .filter(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|n| n.parse().ok())
+            .and_then(Self::from_number)
+            .ok_or_else(|| format!("invalid job mode: [{}]", text))
+    }
+}
+
+impl JobMode {
+    /// Get the variant name of a `JobMode`, as used in JSON (e.g. `ID05`).
+    fn name(self) -> &'static str {
         match self {
-            JobMode::Unknown | JobMode::Offline => false,
-            _ => true,
+            JobMode::Unknown => "Unknown",
+            JobMode::ID01 => "ID01",
+            JobMode::ID02 => "ID02",
+            JobMode::ID03 => "ID03",
+            JobMode::ID04 => "ID04",
+            JobMode::ID05 => "ID05",
+            JobMode::ID06 => "ID06",
+            JobMode::ID07 => "ID07",
+            JobMode::ID08 => "ID08",
+            JobMode::ID09 => "ID09",
+            JobMode::ID10 => "ID10",
+            JobMode::ID11 => "ID11",
+            JobMode::ID12 => "ID12",
+            JobMode::ID13 => "ID13",
+            JobMode::ID14 => "ID14",
+            JobMode::ID15 => "ID15",
+            JobMode::Offline => "Offline",
         }
     }
 }
 
+impl PartialEq<&str> for JobMode {
+    /// Compare a `JobMode` with its variant name, as used in JSON (e.g. `ID05`).
+    ///
+    /// The comparison is case-sensitive.  Bare job mode numbers (e.g. `5`) do not match.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(JobMode::ID05 == "ID05");
+    /// assert!(JobMode::Offline == "Offline");
+    /// assert!(JobMode::Offline != "Off-Line");
+    /// assert!(JobMode::ID05 != "5");
+    /// ~~~
+    fn eq(&self, other: &&str) -> bool {
+        self.name() == *other
+    }
+}
+
+impl PartialEq<JobMode> for &str {
+    fn eq(&self, other: &JobMode) -> bool {
+        *self == other.name()
+    }
+}
+
 impl Default for JobMode {
     /// Default value for `JobMode`.
     fn default() -> Self {
@@ -316,6 +562,42 @@ impl ID {
         Self::try_from(value).ok()
     }
 
+    /// Create a new `ID` from a `u32` value in a `const` context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is zero.  When used to initialize a constant, this becomes
+    /// a compile-time error.
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~compile_fail
+    /// # use ichen_openprotocol::*;
+    /// const ZERO: ID = ID::from_const(0);    // This will not compile.
+    /// # let _ = ZERO;
+    /// ~~~
+    ///
+    /// ~~~should_panic
+    /// # use ichen_openprotocol::*;
+    /// let value = 0;
+    /// let id = ID::from_const(value);    // This will panic.
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// const MACHINE_1: ID = ID::from_const(42);
+    /// assert_eq!(42, MACHINE_1.get());
+    /// assert_eq!(ID::from_u32(42), MACHINE_1);
+    /// ~~~
+    pub const fn from_const(value: u32) -> Self {
+        match NonZeroU32::new(value) {
+            Some(value) => ID(value),
+            None => panic!("ID cannot be zero"),
+        }
+    }
+
     /// Create a new `ID` from a `u32` value.
     ///
     /// # Panics
@@ -416,6 +698,83 @@ impl PartialOrd<ID> for u32 {
     }
 }
 
+/// Check that an operating mode and a job mode are consistent with respect to the controller
+/// being off-line.
+///
+/// When the [operating mode] is `Offline`, the [job mode] cannot be on-line.
+///
+/// The reverse is not enforced: a controller may report an `Offline` job mode while its
+/// operating mode is on-line (e.g. `Manual`), as in the samples in [this document].
+///
+/// [operating mode]: enum.OpMode.html
+/// [job mode]: enum.JobMode.html
+/// [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/cs/doc/messages_reference.md
+///
+/// # Errors
+///
+/// Returns `Err(String)` if `op` is `Offline` but `job` is on-line.
+///
+/// ## Error Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// assert_eq!(
+///     Err("Job mode must be Offline when op mode is Offline, not ID05.".into()),
+///     validate_offline_consistency(OpMode::Offline, JobMode::ID05)
+/// );
+/// ~~~
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// assert_eq!(Ok(()), validate_offline_consistency(OpMode::Offline, JobMode::Offline));
+/// assert_eq!(Ok(()), validate_offline_consistency(OpMode::Automatic, JobMode::ID05));
+/// assert_eq!(Ok(()), validate_offline_consistency(OpMode::Manual, JobMode::Offline));
+/// ~~~
+pub fn validate_offline_consistency(op: OpMode, job: JobMode) -> Result<(), String> {
+    // Job mode cannot be on-line when the controller is off-line
+    if op.is_offline() && job.is_online() {
+        return Err(format!("Job mode must be Offline when op mode is Offline, not {}.", job));
+    }
+
+    Ok(())
+}
+
+/// Categories of machine functions that controller actions belong to.
+///
+/// See [this document] for the list of action codes.
+///
+/// [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/doc/actions.md
+///
+#[derive(Debug, Display, Eq, PartialEq, Hash, Copy, Clone)]
+pub enum ActionCategory {
+    /// Unknown or unused action code.
+    Unknown,
+    /// Idle.
+    Idle,
+    /// Clamp open, including high-pressure release and lock-nuts open.
+    ClampOpen,
+    /// Clamp close, including clamping force, high-pressure close and lock-nuts close.
+    ClampClose,
+    /// Cores in/out.
+    Core,
+    /// Injection.
+    Injection,
+    /// Holding.
+    Holding,
+    /// Plasticizing, including decompression.
+    Plasticizing,
+    /// Purging of the injection/plasticizing unit.
+    Purge,
+    /// Ejector out/in.
+    Ejector,
+    /// Carriage (nozzle) forward/backward.
+    Carriage,
+    /// Auxiliary and special auxiliary functions.
+    Aux,
+}
+
 /// A 32-bit ID that represents a controller action.
 ///
 /// It `Deref`s into an `i32`.
@@ -455,6 +814,66 @@ impl Borrow<i32> for ActionID {
     }
 }
 
+impl ActionID {
+    /// Get the category of the machine function that this action belongs to.
+    ///
+    /// Categories are based on the action codes listed in [this document].
+    /// Unrecognized action codes return `ActionCategory::Unknown`.
+    ///
+    /// [this document]: https://github.com/chenhsong/OpenProtocol/blob/master/doc/actions.md
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(ActionCategory::Idle, ActionID::new(1000).category());
+    /// assert_eq!(ActionCategory::ClampOpen, ActionID::new(2001).category());
+    /// assert_eq!(ActionCategory::ClampClose, ActionID::new(1014).category());
+    /// assert_eq!(ActionCategory::Core, ActionID::new(2094).category());
+    /// assert_eq!(ActionCategory::Injection, ActionID::new(2031).category());
+    /// assert_eq!(ActionCategory::Holding, ActionID::new(1026).category());
+    /// assert_eq!(ActionCategory::Plasticizing, ActionID::new(2052).category());
+    /// assert_eq!(ActionCategory::Purge, ActionID::new(1036).category());
+    /// assert_eq!(ActionCategory::Ejector, ActionID::new(2058).category());
+    /// assert_eq!(ActionCategory::Carriage, ActionID::new(1044).category());
+    /// assert_eq!(ActionCategory::Aux, ActionID::new(2105).category());
+    /// assert_eq!(ActionCategory::Unknown, ActionID::new(1025).category());
+    /// assert_eq!(ActionCategory::Unknown, ActionID::new(42).category());
+    /// ~~~
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn category(&self) -> ActionCategory {
+        match self.0 {
+            // Ai-01 & Ai-02
+            1000 => ActionCategory::Idle,
+            1001..=1005 => ActionCategory::ClampOpen,
+            1006..=1009 | 1015..=1018 | 1045..=1052 => ActionCategory::Core,
+            1010..=1014 | 1019 => ActionCategory::ClampClose,
+            1020..=1024 => ActionCategory::Injection,
+            1026..=1030 => ActionCategory::Holding,
+            1031..=1034 => ActionCategory::Plasticizing,
+            1035..=1037 => ActionCategory::Purge,
+            1038..=1041 => ActionCategory::Ejector,
+            1042..=1044 => ActionCategory::Carriage,
+            1053..=1065 | 1076..=1085 => ActionCategory::Aux,
+            //
+            // Ai-11, Ai-12, CPC-6.0, MPC-6.0 & MPC-7.0
+            2000 => ActionCategory::Idle,
+            2001..=2005 | 2088 | 2089 | 2092 | 2093 => ActionCategory::ClampOpen,
+            2006..=2010 | 2016..=2020 | 2063..=2072 | 2094 | 2095 => ActionCategory::Core,
+            2011..=2015 | 2021 | 2086 | 2087 | 2090 | 2091 => ActionCategory::ClampClose,
+            2022..=2031 => ActionCategory::Injection,
+            2032..=2041 => ActionCategory::Holding,
+            2042..=2052 => ActionCategory::Plasticizing,
+            2053..=2055 => ActionCategory::Purge,
+            2056..=2059 => ActionCategory::Ejector,
+            2060..=2062 => ActionCategory::Carriage,
+            2073..=2085 | 2096..=2105 => ActionCategory::Aux,
+            //
+            _ => ActionCategory::Unknown,
+        }
+    }
+}
+
 impl Debug for ActionID {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", &self.0)
@@ -484,3 +903,115 @@ impl PartialOrd<ActionID> for i32 {
         self.partial_cmp(&other.0)
     }
 }
+
+/// A numeric value that is either an integer or a real number.
+///
+/// When deserializing, an integer is read as `Int` and a decimal number as `Real`.
+/// This preserves the precision of large integer values.
+///
+#[derive(Debug, Display, Copy, Clone, Eq, PartialEq, Hash, From, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NumericValue {
+    /// An integer value.
+    #[display(fmt = "{}", _0)]
+    Int(i64),
+    //
+    /// A real value.
+    #[display(fmt = "{}", _0)]
+    Real(R32),
+}
+
+impl NumericValue {
+    /// Is this value an integer?
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert!(NumericValue::Int(42).is_int());
+    /// assert!(!NumericValue::Real(R32::new(42.0)).is_int());
+    /// ~~~
+    pub fn is_int(self) -> bool {
+        matches!(self, NumericValue::Int(_))
+    }
+
+    /// Get the value as an `f64`.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(42.0, NumericValue::Int(42).as_f64());
+    /// assert_eq!(12.5, NumericValue::Real(R32::new(12.5)).as_f64());
+    /// ~~~
+    pub fn as_f64(self) -> f64 {
+        match self {
+            NumericValue::Int(value) => value as f64,
+            NumericValue::Real(value) => f64::from(value.raw()),
+        }
+    }
+
+    /// Get the value as an `R32`, rounding integers that cannot be represented exactly.
+    ///
+    /// Use `R32::try_from` instead to detect such loss of precision.
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// assert_eq!(R32::new(42.0), NumericValue::Int(42).to_r32_lossy());
+    /// assert_eq!(R32::new(12.5), NumericValue::Real(R32::new(12.5)).to_r32_lossy());
+    /// assert_eq!(R32::new(16_777_216.0), NumericValue::Int(16_777_217).to_r32_lossy());
+    /// ~~~
+    pub fn to_r32_lossy(self) -> R32 {
+        match self {
+            NumericValue::Int(value) => R32::new(value as f32),
+            NumericValue::Real(value) => value,
+        }
+    }
+}
+
+impl TryFrom<NumericValue> for R32 {
+    type Error = String;
+
+    /// Convert a `NumericValue` into an `R32`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(String)` if the value is an integer that cannot be represented exactly
+    /// by an `R32`.  Use [`NumericValue::to_r32_lossy`] to round such integers instead.
+    ///
+    /// [`NumericValue::to_r32_lossy`]: enum.NumericValue.html#method.to_r32_lossy
+    ///
+    /// ## Error Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// assert_eq!(
+    ///     Err("integer value 16777217 cannot be represented exactly as a 32-bit real number".into()),
+    ///     R32::try_from(NumericValue::Int(16_777_217))
+    /// );
+    /// assert!(R32::try_from(NumericValue::Int(std::i64::MAX)).is_err());
+    /// ~~~
+    ///
+    /// # Examples
+    ///
+    /// ~~~
+    /// # use ichen_openprotocol::*;
+    /// # use std::convert::TryFrom;
+    /// assert_eq!(Ok(R32::new(42.0)), R32::try_from(NumericValue::Int(42)));
+    /// assert_eq!(Ok(R32::new(16_777_216.0)), R32::try_from(NumericValue::Int(16_777_216)));
+    /// assert_eq!(Ok(R32::new(12.5)), R32::try_from(NumericValue::Real(R32::new(12.5))));
+    /// ~~~
+    fn try_from(value: NumericValue) -> Result<Self, Self::Error> {
+        match value {
+            // Compare as `i128` because `i64::MAX` rounds up to 2^63, which does not fit in `i64`
+            NumericValue::Int(n) if (n as f32) as i128 != i128::from(n) => Err(format!(
+                "integer value {} cannot be represented exactly as a 32-bit real number",
+                n
+            )),
+            value => Ok(value.to_r32_lossy()),
+        }
+    }
+}
@@ -1,12 +1,48 @@
-use super::ID;
+use super::{ParseOptions, TextID, ID, R32};
 use indexmap::IndexMap;
+use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+use std::cell::Cell;
 use std::convert::TryInto;
-use std::fmt::Display;
+use std::fmt::{Display, Formatter};
 use std::hash::Hash;
+use std::marker::PhantomData;
 use std::num::NonZeroU32;
 use std::str::FromStr;
 
+thread_local! {
+    /// Maximum number of entries allowed in a data dictionary during deserialization
+    /// on the current thread.  See [`with_max_map_entries`].
+    ///
+    /// [`with_max_map_entries`]: fn.with_max_map_entries.html
+    //
+    // `const` thread-local initializers require Rust 1.59
+    #[allow(clippy::missing_const_for_thread_local)]
+    static MAX_MAP_ENTRIES: Cell<usize> = Cell::new(ParseOptions::DEFAULT_MAX_MAP_ENTRIES);
+}
+
+/// Run a closure with the maximum number of entries allowed in a data dictionary
+/// (enforced by [`deserialize_bounded_indexmap`]) set to `max` on the current thread.
+///
+/// The previous limit is restored afterwards, even if the closure panics.
+///
+/// [`deserialize_bounded_indexmap`]: fn.deserialize_bounded_indexmap.html
+///
+pub fn with_max_map_entries<T>(max: usize, f: impl FnOnce() -> T) -> T {
+    struct Restore(usize);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MAX_MAP_ENTRIES.with(|limit| limit.set(self.0));
+        }
+    }
+
+    let _restore = Restore(MAX_MAP_ENTRIES.with(|limit| limit.replace(max)));
+    f()
+}
+
 /// A trait to specify different _invalid_ values for a type for serialization purposes
 pub trait HasInvalidValue {
     type Marker;
@@ -38,7 +74,7 @@ impl HasInvalidValue for f32 {
 
     /// Use NaN as an invalid value for floating-point numbers.
     fn invalid() -> Self::Marker {
-        std::f32::NAN
+        f32::NAN
     }
 }
 
@@ -47,7 +83,7 @@ impl HasInvalidValue for f64 {
 
     /// Use NaN as an invalid value for floating-point numbers.
     fn invalid() -> Self::Marker {
-        std::f64::NAN
+        f64::NAN
     }
 }
 
@@ -76,6 +112,135 @@ pub fn check_f32(value: f32) -> std::result::Result<(), &'static str> {
     }
 }
 
+/// Create an `R32` from an `f64` value (e.g. a computed value), narrowing it to `f32`.
+///
+/// Unlike `R32::new`, this does not panic on non-numeric values.
+///
+/// # Errors
+///
+/// Returns `Err(String)` if `value` is not a normal number (e.g. `NaN`, `Infinity`) after
+/// narrowing to `f32` (e.g. an `f64` value too large for `f32` narrows to `Infinity`).
+///
+/// ## Error Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// assert_eq!(Err("NaN is not a supported value".into()), r32_from_f64_checked(std::f64::NAN));
+/// assert_eq!(Err("Infinity is not a supported value".into()), r32_from_f64_checked(std::f64::INFINITY));
+/// assert_eq!(Err("Infinity is not a supported value".into()), r32_from_f64_checked(1.0e100));
+/// ~~~
+///
+/// # Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// # fn main() -> std::result::Result<(), String> {
+/// assert_eq!(12.5, r32_from_f64_checked(12.5)?.raw());
+/// assert_eq!(-0.25, r32_from_f64_checked(-0.25)?.raw());
+/// # Ok(())
+/// # }
+/// ~~~
+pub fn r32_from_f64_checked(value: f64) -> std::result::Result<R32, String> {
+    let value = value as f32;
+    check_f32(value)?;
+    Ok(R32::new(value))
+}
+
+/// Build a data dictionary (e.g. for the `data` field of `CycleData` and `MoldData` messages)
+/// from a list of (key, value) pairs.
+///
+/// Each key must be a valid `TextID` and each value must be a normal number.
+///
+/// # Errors
+///
+/// Returns `Err(String)` if any key is not a valid `TextID`, or if any value is not
+/// a normal number (e.g. `NaN`, `Infinity`).
+///
+/// ## Error Examples
+///
+/// ~~~
+/// # use ichen_openprotocol::*;
+/// assert_eq!(
+///     Err("invalid value: a non-empty, non-whitespace, all-ASCII string required for key [溫度]".into()),
+///     build_data_map(vec![("Z_QDGODCNT", 123.0), ("溫度", 1.0)])
+/// );
+///
+/// assert_eq!(
+///     Err("invalid value: a non-empty, non-whitespace, all-ASCII string required for key []".into()),
+///     build_data_map(vec![("", 1.0)])
+/// );
+///
+/// assert_eq!(
+///     Err("NaN is not a supported value for [Z_QDCYCTIM]".into()),
+///     build_data_map(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", std::f32::NAN)])
+/// );
+/// ~~~
+///
+/// # Examples
+///
+#[cfg_attr(feature = "chrono", doc = " ~~~")]
+#[cfg_attr(not(feature = "chrono"), doc = " ~~~ignore")]
+/// # use ichen_openprotocol::*;
+/// # use chrono::DateTime;
+/// # fn main() -> std::result::Result<(), String> {
+/// let data = build_data_map(vec![("Z_QDGODCNT", 123.0), ("Z_QDCYCTIM", 12.5), ("Z_QDINJTIM", 0.0)])?;
+///
+/// assert_eq!(3, data.len());
+/// assert_eq!(12.5, data["Z_QDCYCTIM"].raw());
+/// assert_eq!(vec!["Z_QDGODCNT", "Z_QDCYCTIM", "Z_QDINJTIM"], data.keys().map(|k| k.get()).collect::<Vec<_>>());
+///
+/// let msg = Message::new_cycle_data_simple(
+///     ID::from_u32(123),
+///     data,
+///     DateTime::parse_from_rfc3339("2019-02-26T02:03:04+08:00").unwrap(),
+///     OpMode::Automatic,
+///     JobMode::ID02,
+/// );
+/// assert_eq!(Ok(()), msg.validate());
+/// # Ok(())
+/// # }
+/// ~~~
+pub fn build_data_map<'a>(
+    entries: impl IntoIterator<Item = (&'a str, f32)>,
+) -> Result<IndexMap<TextID<'a>, R32>, String> {
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let key: TextID = key.try_into().map_err(|e| format!("{} for key [{}]", e, key))?;
+            check_f32(value).map_err(|e| format!("{} for [{}]", e, key))?;
+            Ok((key, R32::new(value)))
+        })
+        .collect()
+}
+
+/// Deserialize a string into a `Cow<str>`, borrowing from the input whenever possible.
+///
+/// Strings that cannot be borrowed (e.g. those containing escape sequences, or when the input
+/// is read from a stream) are copied into an owned `String` instead.
+///
+pub fn deserialize_cow_str<'a, 'de: 'a, D>(d: D) -> Result<Cow<'a, str>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Wrapper::deserialize(d).map(|Wrapper(s)| s)
+}
+
+/// Deserialize a string or `null` into an `Option<Cow<str>>`, borrowing from the input
+/// whenever possible.
+///
+pub fn deserialize_optional_cow_str<'a, 'de: 'a, D>(d: D) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Wrapper<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Option::<Wrapper>::deserialize(d).map(|s| s.map(|Wrapper(s)| s))
+}
+
 /// Deserialize a JSON `null` value as `Some(None)` instead of `None`.
 #[allow(clippy::option_option)]
 pub fn deserialize_null_to_some_none<'de, D, T>(d: D) -> Result<Option<Option<T>>, D::Error>
@@ -122,9 +287,114 @@ where
     }
 }
 
+/// Deserialize an optional boolean that may also be expressed as the integer `0` or `1`.
+pub fn deserialize_bool_or_int<'de, D>(d: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrInt {
+        Bool(bool),
+        Int(i64),
+    }
+
+    match Option::<BoolOrInt>::deserialize(d)? {
+        None => Ok(None),
+        Some(BoolOrInt::Bool(value)) => Ok(Some(value)),
+        Some(BoolOrInt::Int(0)) => Ok(Some(false)),
+        Some(BoolOrInt::Int(1)) => Ok(Some(true)),
+        Some(BoolOrInt::Int(value)) => {
+            Err(serde::de::Error::custom(format!("expected a boolean or 0/1, got [{}]", value)))
+        }
+    }
+}
+
+/// Deserialize an `ID` that may also be expressed as a numeric string (e.g. `"123"`).
+pub fn deserialize_id_or_string<'de, D>(d: D) -> Result<ID, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum IdOrString {
+        Id(u32),
+        Text(String),
+    }
+
+    let value = match IdOrString::deserialize(d)? {
+        IdOrString::Id(value) => value,
+        IdOrString::Text(text) => text.trim().parse().map_err(|_| {
+            serde::de::Error::custom(format!("expected an ID or a numeric string, got [{}]", text))
+        })?,
+    };
+
+    value.try_into().map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an `IndexMap`, failing if it contains more entries than allowed.
+///
+/// The limit defaults to [`ParseOptions::DEFAULT_MAX_MAP_ENTRIES`] and can be changed via
+/// [`ParseOptions::max_map_entries`].
+///
+/// Within a `Message`, the entries are already buffered by the time this is called, so this
+/// limits the size of the resulting map only, not the memory used during parsing.
+///
+/// [`ParseOptions::DEFAULT_MAX_MAP_ENTRIES`]: struct.ParseOptions.html#associatedconstant.DEFAULT_MAX_MAP_ENTRIES
+/// [`ParseOptions::max_map_entries`]: struct.ParseOptions.html#structfield.max_map_entries
+///
+pub fn deserialize_bounded_indexmap<'de, D, K, T>(d: D) -> Result<IndexMap<K, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de> + Eq + Hash,
+    T: Deserialize<'de>,
+{
+    struct BoundedMapVisitor<K, T>(usize, PhantomData<(K, T)>);
+
+    impl<'de, K, T> Visitor<'de> for BoundedMapVisitor<K, T>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        T: Deserialize<'de>,
+    {
+        type Value = IndexMap<K, T>;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(f, "a map with at most {} entries", self.0)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+            let capacity = access.size_hint().unwrap_or(0).min(self.0);
+            let mut map = IndexMap::with_capacity(capacity);
+            let mut count = 0;
+
+            while let Some((key, value)) = access.next_entry()? {
+                count += 1;
+
+                if count > self.0 {
+                    return Err(serde::de::Error::custom(format!(
+                        "too many entries: a maximum of {} entries is allowed",
+                        self.0
+                    )));
+                }
+
+                map.insert(key, value);
+            }
+
+            Ok(map)
+        }
+    }
+
+    let max = MAX_MAP_ENTRIES.with(Cell::get);
+    d.deserialize_map(BoundedMapVisitor(max, PhantomData))
+}
+
 /// Deserialize an `IndexMap` with keys that are not `String` (but is of a type
 /// that implements `FromStr`).
 ///
+/// The number of entries is limited as in [`deserialize_bounded_indexmap`].
+///
+/// [`deserialize_bounded_indexmap`]: fn.deserialize_bounded_indexmap.html
+///
 /// Serialization is usually not a problem because `serde_json` automatically calls
 /// `to_string()` (for key types that implement `Display`) when serializing.
 ///
@@ -141,8 +411,8 @@ where
         S: FromStr,
         S::Err: Display,
     {
-        let s = Deserialize::deserialize(d).map_err(serde::de::Error::custom)?;
-        S::from_str(s).map_err(|err| serde::de::Error::custom(format!("{}: {}", err, s)))
+        let s = deserialize_cow_str(d)?;
+        S::from_str(&s).map_err(|err| serde::de::Error::custom(format!("{}: {}", err, s)))
     }
 
     #[derive(Deserialize, Hash, Eq, PartialEq)]
@@ -151,6 +421,175 @@ where
         S: FromStr,
         S::Err: Display;
 
-    let dict: IndexMap<Wrapper<K>, T> = Deserialize::deserialize(d)?;
+    let dict: IndexMap<Wrapper<K>, T> = deserialize_bounded_indexmap(d)?;
     Ok(dict.into_iter().map(|(Wrapper(k), v)| (k, v)).collect())
 }
+
+/// Remove a leading UTF-8 byte-order mark (BOM) from a JSON string, if any.
+pub fn strip_bom(json: &str) -> &str {
+    json.strip_prefix('\u{FEFF}').unwrap_or(json)
+}
+
+/// Parse the top-level fields of a JSON object, in order, keeping their values as raw JSON.
+///
+/// This is used to rewrite the fields of a JSON object (e.g. renaming the `$type` field)
+/// without parsing the values.
+///
+pub fn raw_fields(json: &str) -> serde_json::Result<IndexMap<Cow<'_, str>, &RawValue>> {
+    serde_json::from_str(json)
+}
+
+/// Rename a field (if it exists) in a set of raw JSON fields, keeping the order of all fields.
+pub fn rename_raw_field<'a, 'b>(
+    fields: IndexMap<Cow<'a, str>, &'b RawValue>,
+    from: &str,
+    to: &'a str,
+) -> IndexMap<Cow<'a, str>, &'b RawValue> {
+    fields
+        .into_iter()
+        .map(|(k, v)| if k == from { (Cow::Borrowed(to), v) } else { (k, v) })
+        .collect()
+}
+
+/// Check whether JSON objects and arrays in a JSON string nest deeper than `max_depth`,
+/// ignoring brackets inside string literals.
+///
+/// The JSON text is not otherwise validated.
+///
+pub fn json_depth_exceeds(json: &str, max_depth: usize) -> bool {
+    let mut depth = 0_usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for ch in json.chars() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '{' | '[' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    false
+}
+
+/// Blank out comments (`// ...` and `/* ... */`) and trailing commas (e.g. `[1, 2,]`) in
+/// a JSON string, leaving string literals intact.
+///
+/// Removed text is replaced by the same number of white-spaces (keeping new-lines), so
+/// line and column numbers in the result match those in the original string.
+///
+/// Returns `None` if there is nothing to remove.
+///
+#[cfg(feature = "lenient-json")]
+pub fn strip_lenient_json(json: &str) -> Option<String> {
+    // Blank out a character, keeping new-lines and the byte length
+    fn blank(text: &mut String, ch: char) {
+        if ch == '\n' {
+            text.push(ch);
+        } else {
+            text.push_str(&" ".repeat(ch.len_utf8()));
+        }
+    }
+
+    // Pass 1: replace comments with white-spaces
+    let mut text = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+    let mut changed = false;
+
+    while let Some(ch) = chars.next() {
+        if in_string {
+            text.push(ch);
+
+            match ch {
+                '\\' => text.extend(chars.next()),
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match (ch, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                text.push(ch);
+            }
+            ('/', Some('/')) => {
+                changed = true;
+                blank(&mut text, ch);
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    blank(&mut text, c);
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                changed = true;
+                blank(&mut text, ch);
+                blank(&mut text, chars.next().unwrap());
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    blank(&mut text, c);
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => text.push(ch),
+        }
+    }
+
+    // Pass 2: blank out commas followed by a closing bracket
+    let mut result = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, ch) in text.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+        } else if ch == '"' {
+            in_string = true;
+        } else if ch == ',' {
+            match text[i + 1..].trim_start().chars().next() {
+                Some('}') | Some(']') => {
+                    changed = true;
+                    result.push(' ');
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        result.push(ch);
+    }
+
+    if changed {
+        Some(result)
+    } else {
+        None
+    }
+}